@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::{
+    data::storage::{storage_error, AppState},
+    publishing, scoring,
+};
+
+pub fn restricted_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/recalculate", post(recalculate))
+        .route("/api/admin/rankings/publish", post(publish_rankings))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RecalculateOptions {
+    #[serde(default)]
+    apply: bool,
+    #[serde(default)]
+    trial: bool,
+}
+
+/// Replay the full history and report what differs from the stored values.
+/// The recalculated competitions and rankings are only saved with `?apply=true`.
+/// With `?trial=true` the replay uses the config's trial rules, which can't be
+/// applied.
+async fn recalculate(
+    State(state): State<AppState>,
+    Query(options): Query<RecalculateOptions>,
+) -> Response {
+    let _writing = match options.apply {
+        true => Some(state.writes.lock().await),
+        false => None,
+    };
+    let (rankings, competitions, pilots) = match (
+        state.storage.rankings(),
+        state.storage.competitions(),
+        state.storage.pilots(),
+    ) {
+        (Ok(rankings), Ok(competitions), Ok(pilots)) => (rankings, competitions, pilots),
+        (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => {
+            return storage_error(error)
+        }
+    };
+    let trial_config = match (options.trial, options.apply) {
+        (false, _) => None,
+        (true, true) => {
+            return (StatusCode::BAD_REQUEST, "Trial rules can't be applied").into_response()
+        }
+        (true, false) => match state.scoring.with_trial() {
+            Some(config) => Some(config),
+            None => return (StatusCode::BAD_REQUEST, "There are no trial rules").into_response(),
+        },
+    };
+    let config = trial_config.as_ref().unwrap_or(&state.scoring);
+    let (new_competitions, new_rankings) =
+        scoring::recalculate_all(&competitions, &rankings, &pilots, config);
+    let mut summary = scoring::summarise_recalculation(
+        &competitions,
+        &rankings,
+        &new_competitions,
+        &new_rankings,
+    );
+    if options.apply {
+        if let Err(error) = state.storage.save_all(&new_competitions, &new_rankings) {
+            return storage_error(error);
+        }
+        summary.applied = true;
+    }
+    Json(summary).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct PublishOptions {
+    from: String,
+    to: Option<String>,
+    #[serde(default)]
+    force: bool,
+}
+
+/// Publish the monthly rankings from `from` up to `to` (the current month by
+/// default). Months that already have a ranking are skipped unless `force=true`
+async fn publish_rankings(
+    State(state): State<AppState>,
+    Query(options): Query<PublishOptions>,
+) -> Response {
+    let from = match options.from.parse::<NaiveDate>() {
+        Ok(from) => from,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Invalid from date").into_response(),
+    };
+    let to = match options.to.map(|to| to.parse::<NaiveDate>()) {
+        Some(Ok(to)) => to,
+        Some(Err(_)) => return (StatusCode::BAD_REQUEST, "Invalid to date").into_response(),
+        None => publishing::current_ranking_month(),
+    };
+    if from > to {
+        return (StatusCode::BAD_REQUEST, "from must not be after to").into_response();
+    }
+    let _writing = state.writes.lock().await;
+    match publishing::backfill_rankings(&state, from, to, options.force) {
+        Ok(published) => Json(published).into_response(),
+        Err(error) => storage_error(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::data_access::load_data;
+
+    #[tokio::test]
+    async fn recalculate_should_return_summary() {
+        let result = recalculate(
+            State(load_data().unwrap().into()),
+            Query(RecalculateOptions::default()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn trial_recalculation_should_not_be_applied() {
+        let result = recalculate(
+            State(load_data().unwrap().into()),
+            Query(RecalculateOptions {
+                apply: true,
+                trial: true,
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        // The default config has no trial rules
+        let result = recalculate(
+            State(load_data().unwrap().into()),
+            Query(RecalculateOptions {
+                apply: false,
+                trial: true,
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn publish_rankings_should_return_result() {
+        let result = publish_rankings(
+            State(load_data().unwrap().into()),
+            Query(PublishOptions {
+                from: "2022-01-01".to_string(),
+                to: Some("2022-03-01".to_string()),
+                force: false,
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+}
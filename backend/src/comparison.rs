@@ -0,0 +1,313 @@
+use chrono::{Months, NaiveDate};
+use frontend::prs_data_types::{
+    CompResult, ComparedCompetition, Competition, PilotMovement, Ranking, RankingComparison,
+    RankingPoint, ResultStatus,
+};
+
+use crate::{
+    data::scoring_config::{ScoringConfig, ScoringRules},
+    scoring,
+};
+
+/// What changed from one ranking to a later one in the same discipline: who
+/// joined and left the list, who moved, where the points came from and went,
+/// and which competitions entered or left the ranking window
+pub fn compare_rankings(
+    from: &Ranking,
+    to: &Ranking,
+    competitions: &[Competition],
+    config: &ScoringConfig,
+) -> Option<RankingComparison> {
+    let from_date = from.date.parse::<NaiveDate>().ok()?;
+    let to_date = to.date.parse::<NaiveDate>().ok()?;
+    let from_points = scoring::positioned_points(from);
+    let to_points = scoring::positioned_points(to);
+    let find = |points: &[RankingPoint], pin: &str| -> Option<RankingPoint> {
+        points.iter().find(|p| p.pilot_pin == pin).cloned()
+    };
+    let (from_rules, to_rules) = (config.rules_for(&from_date), config.rules_for(&to_date));
+    let movements: Vec<PilotMovement> = to_points
+        .iter()
+        .map(|point| {
+            let from_point = find(&from_points, &point.pilot_pin);
+            pilot_movement(from_point.as_ref(), Some(point), from_rules, to_rules)
+        })
+        .chain(
+            from_points
+                .iter()
+                .filter(|point| find(&to_points, &point.pilot_pin).is_none())
+                .map(|point| pilot_movement(Some(point), None, from_rules, to_rules)),
+        )
+        .collect();
+    let mut climbers: Vec<PilotMovement> = movements
+        .iter()
+        .filter(|m| m.movement.is_some_and(|movement| movement > 0))
+        .cloned()
+        .collect();
+    climbers.sort_by_key(|m| std::cmp::Reverse(m.movement));
+    let mut fallers: Vec<PilotMovement> = movements
+        .iter()
+        .filter(|m| m.movement.is_some_and(|movement| movement < 0))
+        .cloned()
+        .collect();
+    fallers.sort_by_key(|m| m.movement);
+
+    let window_start = |date: &NaiveDate| {
+        date.checked_sub_months(Months::new(config.rules_for(date).ranking_months))
+    };
+    let (from_start, to_start) = (window_start(&from_date)?, window_start(&to_date)?);
+    let compared_competitions = |in_from: bool, in_to: bool| -> Vec<ComparedCompetition> {
+        competitions
+            .iter()
+            .filter(|c| c.discipline == to.discipline)
+            .filter(|c| {
+                scoring::in_ranking_window(c, &from_start, &from_date) == in_from
+                    && scoring::in_ranking_window(c, &to_start, &to_date) == in_to
+            })
+            .map(|c| ComparedCompetition {
+                comp_id: c.id.clone(),
+                comp_name: c.name.clone(),
+                comp_date: c.comp_date.clone(),
+            })
+            .collect()
+    };
+
+    Some(RankingComparison {
+        from: from.date.clone(),
+        to: to.date.clone(),
+        discipline: to.discipline,
+        new_entries: movements
+            .iter()
+            .filter(|m| m.from_position.is_none())
+            .cloned()
+            .collect(),
+        dropped_out: movements
+            .iter()
+            .filter(|m| m.to_position.is_none())
+            .cloned()
+            .collect(),
+        climbers,
+        fallers,
+        points_from_new_competitions: movements
+            .iter()
+            .map(|m| m.points_from_new_competitions)
+            .sum(),
+        points_lost_to_decay: movements.iter().map(|m| m.points_lost_to_decay).sum(),
+        points_dropped: movements.iter().map(|m| m.points_dropped).sum(),
+        competitions_added: compared_competitions(false, true),
+        competitions_aged_out: compared_competitions(true, false),
+    })
+}
+
+/// A pilot's change between their places in two rankings, either may be
+/// missing when they joined or left the list. Only the results counted under
+/// each ranking's rules are compared, so the points gained less those lost to
+/// decay and dropped add up to the change in total points.
+pub fn pilot_movement(
+    from: Option<&RankingPoint>,
+    to: Option<&RankingPoint>,
+    from_rules: &ScoringRules,
+    to_rules: &ScoringRules,
+) -> PilotMovement {
+    let pilot = to.or(from).cloned().unwrap_or_default();
+    let from_results = counted_results(from, from_rules);
+    let to_results = counted_results(to, to_rules);
+    let earlier = |comp_id: &str| from_results.iter().find(|r| r.comp_id == comp_id);
+    let later = |comp_id: &str| to_results.iter().find(|r| r.comp_id == comp_id);
+    PilotMovement {
+        pilot_pin: pilot.pilot_pin,
+        pilot_first_name: pilot.pilot_first_name,
+        pilot_last_name: pilot.pilot_last_name,
+        from_position: from.map(|p| p.position),
+        to_position: to.map(|p| p.position),
+        from_points: from.map_or(0.0, |p| p.total_points),
+        to_points: to.map_or(0.0, |p| p.total_points),
+        movement: match (from, to) {
+            (Some(from), Some(to)) => Some(from.position as i64 - to.position as i64),
+            _ => None,
+        },
+        points_from_new_competitions: to_results
+            .iter()
+            .filter(|r| earlier(&r.comp_id).is_none())
+            .map(|r| r.points)
+            .sum(),
+        points_lost_to_decay: to_results
+            .iter()
+            .filter_map(|r| earlier(&r.comp_id).map(|e| e.points - r.points))
+            .sum(),
+        points_dropped: from_results
+            .iter()
+            .filter(|r| later(&r.comp_id).is_none())
+            .map(|r| r.points)
+            .sum(),
+    }
+}
+
+/// The results counted towards the pilot's total under the rules
+fn counted_results(point: Option<&RankingPoint>, rules: &ScoringRules) -> Vec<CompResult> {
+    point.map_or(vec![], |point| {
+        scoring::select_results(point.results.clone(), rules)
+            .into_iter()
+            .filter(|(_, status)| *status == ResultStatus::Counted)
+            .map(|(result, _)| result)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking(date: &str, points: &[(&str, &[(&str, f64)])]) -> Ranking {
+        Ranking {
+            date: date.to_string(),
+            ranking_points: points
+                .iter()
+                .map(|(pin, results)| RankingPoint {
+                    pilot_pin: pin.to_string(),
+                    total_points: results.iter().map(|(_, points)| points).sum(),
+                    results: results
+                        .iter()
+                        .map(|(comp_id, points)| CompResult {
+                            comp_id: comp_id.to_string(),
+                            points: *points,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn competition(id: &str, comp_date: &str) -> Competition {
+        Competition {
+            id: id.to_string(),
+            comp_date: comp_date.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn comparison_should_show_movers_and_window_changes() {
+        let from = ranking(
+            "2022-01-01",
+            &[
+                ("1001", &[("old", 50.0), ("mid", 40.0)]),
+                ("1002", &[("mid", 60.0)]),
+                ("1003", &[("old", 10.0)]),
+            ],
+        );
+        let to = ranking(
+            "2022-02-01",
+            &[
+                ("1001", &[("mid", 38.0), ("new", 80.0)]),
+                ("1002", &[("mid", 57.0)]),
+                ("1004", &[("new", 20.0)]),
+            ],
+        );
+        let competitions = vec![
+            competition("old", "2019-01-15"),
+            competition("mid", "2021-06-01"),
+            competition("new", "2022-01-10"),
+        ];
+        let comparison =
+            compare_rankings(&from, &to, &competitions, &ScoringConfig::default()).unwrap();
+        let pins = |movements: &[PilotMovement]| {
+            movements
+                .iter()
+                .map(|m| m.pilot_pin.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(pins(&comparison.new_entries), vec!["1004"]);
+        assert_eq!(pins(&comparison.dropped_out), vec!["1003"]);
+        assert!(comparison.climbers.is_empty());
+        assert!(comparison.fallers.is_empty());
+        assert_eq!(comparison.points_from_new_competitions, 100.0);
+        assert_eq!(comparison.points_lost_to_decay, 5.0);
+        assert_eq!(comparison.points_dropped, 60.0);
+        assert_eq!(comparison.competitions_added[0].comp_id, "new");
+        assert_eq!(comparison.competitions_aged_out[0].comp_id, "old");
+
+        let later = ranking(
+            "2022-03-01",
+            &[
+                ("1004", &[("new", 200.0)]),
+                ("1001", &[("new", 80.0)]),
+                ("1002", &[("mid", 55.0)]),
+            ],
+        );
+        let comparison =
+            compare_rankings(&to, &later, &competitions, &ScoringConfig::default()).unwrap();
+        assert_eq!(pins(&comparison.climbers), vec!["1004"]);
+        assert_eq!(comparison.climbers[0].movement, Some(2));
+        assert_eq!(pins(&comparison.fallers), vec!["1001", "1002"]);
+    }
+
+    #[test]
+    fn movement_should_only_count_best_results() {
+        let point = |total_points: f64, results: &[(&str, f64)]| RankingPoint {
+            pilot_pin: "1001".to_string(),
+            total_points,
+            results: results
+                .iter()
+                .map(|(comp_id, points)| CompResult {
+                    comp_id: comp_id.to_string(),
+                    points: *points,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let rules = ScoringRules::default();
+        let from = point(
+            140.0,
+            &[
+                ("a", 50.0),
+                ("b", 40.0),
+                ("c", 30.0),
+                ("d", 20.0),
+                ("e", 5.0),
+            ],
+        );
+        let fifth_best = point(
+            135.0,
+            &[
+                ("a", 45.0),
+                ("b", 40.0),
+                ("c", 30.0),
+                ("d", 20.0),
+                ("e", 5.0),
+                ("f", 15.0),
+            ],
+        );
+        let movement = pilot_movement(Some(&from), Some(&fifth_best), &rules, &rules);
+        assert_eq!(movement.points_from_new_competitions, 0.0);
+        assert_eq!(movement.points_lost_to_decay, 5.0);
+        assert_eq!(movement.points_dropped, 0.0);
+
+        let pushed_out = point(
+            150.0,
+            &[
+                ("a", 45.0),
+                ("b", 40.0),
+                ("g", 35.0),
+                ("c", 30.0),
+                ("d", 20.0),
+                ("e", 5.0),
+            ],
+        );
+        let movement = pilot_movement(Some(&from), Some(&pushed_out), &rules, &rules);
+        assert_eq!(movement.points_from_new_competitions, 35.0);
+        assert_eq!(movement.points_lost_to_decay, 5.0);
+        assert_eq!(movement.points_dropped, 20.0);
+        assert_eq!(
+            movement.from_points + movement.points_from_new_competitions
+                - movement.points_lost_to_decay
+                - movement.points_dropped,
+            movement.to_points
+        );
+    }
+}
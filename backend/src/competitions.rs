@@ -1,66 +1,272 @@
 use axum::{
-    extract::{self, Path, State},
+    extract::{self, Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use chrono::{Months, NaiveDate};
-use frontend::prs_data_types::{Competition, Root};
+use frontend::prs_data_types::{
+    Competition, CreatedCompetition, Discipline, ForecastRequest, SimulationRequest, TaskQuality,
+};
+use serde::Deserialize;
 use validator::Validate;
 
-use crate::scoring;
+use crate::{
+    data::storage::{storage_error, AppState},
+    publishing::ranking_month,
+    rankings::DisciplineQuery,
+    scoring, simulation,
+};
 
-pub fn competition_routes() -> Router<Root> {
+pub fn competition_routes() -> Router<AppState> {
     Router::new()
         .route("/api/competitions", get(competitions))
         .route("/api/competition/:id", get(competition))
+        .route("/api/exchangerate/:date", get(exchange_rate))
+        .route("/api/taskquality/:date", get(task_quality))
+        .route("/api/competitions/simulate", post(simulate_competition))
+        .route("/api/competitions/forecast", post(forecast_competition))
 }
 
-pub fn restricted_competition_routes() -> Router<Root> {
+pub fn restricted_competition_routes() -> Router<AppState> {
     Router::new().route("/api/competitions", post(create_competition))
 }
 
-async fn competitions(State(data): State<Root>) -> Response {
-    let mut sorted_competitions = data.competitions.clone();
-    sorted_competitions.sort_by(|a, b| b.comp_date.cmp(&a.comp_date));
-    Json(&sorted_competitions).into_response()
+async fn competitions(State(state): State<AppState>) -> Response {
+    match state.storage.competitions() {
+        Ok(mut sorted_competitions) => {
+            sorted_competitions.sort_by(|a, b| b.comp_date.cmp(&a.comp_date));
+            Json(&sorted_competitions).into_response()
+        }
+        Err(error) => storage_error(error),
+    }
 }
 
-async fn competition(State(data): State<Root>, Path(id): extract::Path<String>) -> Response {
+async fn competition(State(state): State<AppState>, Path(id): extract::Path<String>) -> Response {
     tracing::info!("Competition {:?} requested", id);
-    match data.competitions.iter().find(|c| c.id == id) {
-        Some(competition) => Json(competition).into_response(),
-        None => (StatusCode::NOT_FOUND).into_response(),
+    match state.storage.competition(&id) {
+        Ok(Some(competition)) => Json(competition).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND).into_response(),
+        Err(error) => storage_error(error),
+    }
+}
+
+/// The suggested exchange rate for an overseas competition in the discipline on
+/// the date and the NZ competitions it was worked out from
+async fn exchange_rate(
+    State(state): State<AppState>,
+    Path(date): extract::Path<String>,
+    Query(query): Query<DisciplineQuery>,
+) -> Response {
+    let date = match date.parse::<NaiveDate>() {
+        Ok(date) => date,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+    };
+    match state.storage.competitions() {
+        Ok(competitions) => Json(scoring::exchange_rate(
+            &date,
+            query.discipline,
+            &competitions,
+            &state.scoring,
+        ))
+        .into_response(),
+        Err(error) => storage_error(error),
     }
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskQualityQuery {
+    #[serde(default)]
+    pub discipline: Discipline,
+    pub num_tasks: usize,
+    #[serde(default)]
+    pub total_validity: Option<f64>,
+}
+
+/// Ta for a competition on the date by its number of tasks and by its summed
+/// task validity, and which of the two the rules in force use
+async fn task_quality(
+    State(state): State<AppState>,
+    Path(date): extract::Path<String>,
+    Query(query): Query<TaskQualityQuery>,
+) -> Response {
+    let date = match date.parse::<NaiveDate>() {
+        Ok(date) => date,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+    };
+    let rules = state.scoring.rules_for(&date);
+    Json(TaskQuality {
+        mode: rules.ta_mode,
+        by_task_count: rules.task_quality(query.discipline, query.num_tasks),
+        by_validity: query
+            .total_validity
+            .map(|validity| rules.validity_task_quality(query.discipline, validity)),
+    })
+    .into_response()
+}
+
+/// Score and save a competition, recalculating the later competitions and
+/// rankings it changes, and report what changed
 async fn create_competition(
-    State(data): State<Root>,
-    Json(competition): extract::Json<Competition>,
+    State(state): State<AppState>,
+    Json(mut competition): extract::Json<Competition>,
 ) -> Response {
-    let mut rankings = data.rankings.clone();
     match competition.validate() {
         Err(error) => (StatusCode::BAD_REQUEST, Json(error)).into_response(),
         Ok(_) => {
-            rankings.sort_by(|a, b| b.date.cmp(&a.date));
-            let ranking = rankings.iter().find(|r| {
-                let comp_date = competition.comp_date.parse::<NaiveDate>().unwrap();
-                let rdate = &&r.date.parse::<NaiveDate>().unwrap();
-                let two_years_earlier = comp_date.checked_sub_months(Months::new(24)).unwrap();
-                two_years_earlier.lt(&rdate) && (comp_date.gt(&rdate) || comp_date.eq(&rdate))
-            });
-            match scoring::recalculate_competition(&competition, ranking, &data.competitions) {
-                Some(new_competition) => Json(new_competition).into_response(),
-                None => (StatusCode::BAD_REQUEST).into_response(),
+            let _writing = state.writes.lock().await;
+            if competition.id.trim().is_empty() {
+                competition.id = format!("{}-{}", competition.comp_date, competition.location);
+            }
+            let (rankings, competitions, pilots) = match (
+                state.storage.rankings(),
+                state.storage.competitions(),
+                state.storage.pilots(),
+            ) {
+                (Ok(rankings), Ok(competitions), Ok(pilots)) => (rankings, competitions, pilots),
+                (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => {
+                    return storage_error(error)
+                }
+            };
+            let ranking = scoring::prior_ranking(&competition, &rankings, &state.scoring);
+            if scoring::recalculate_competition(
+                &competition,
+                ranking,
+                &competitions,
+                &state.scoring,
+            )
+            .is_none()
+            {
+                return (StatusCode::BAD_REQUEST).into_response();
+            }
+            // A back-dated competition changes the later competitions and
+            // rankings it feeds into
+            let (new_competitions, new_rankings) = scoring::recalculate_with_competition(
+                &competition,
+                &competitions,
+                &rankings,
+                &pilots,
+                &state.scoring,
+            );
+            let mut summary = scoring::summarise_recalculation(
+                &competitions,
+                &rankings,
+                &new_competitions,
+                &new_rankings,
+            );
+            let saved = match summary.competitions.is_empty() && summary.rankings.is_empty() {
+                true => new_competitions
+                    .iter()
+                    .find(|c| c.id == competition.id)
+                    .map_or(Ok(()), |c| state.storage.save_competition(c)),
+                false => {
+                    tracing::info!(
+                        "Competition {} changed the rankings {:?}",
+                        competition.id,
+                        summary
+                            .rankings
+                            .iter()
+                            .map(|r| format!("{} {}", r.date, r.discipline))
+                            .collect::<Vec<_>>()
+                    );
+                    state.storage.save_all(&new_competitions, &new_rankings)
+                }
+            };
+            summary.applied = saved.is_ok();
+            match (
+                saved,
+                new_competitions
+                    .into_iter()
+                    .find(|c| c.id == competition.id),
+            ) {
+                (Ok(_), Some(new_competition)) => Json(CreatedCompetition {
+                    competition: new_competition,
+                    recalculation: summary,
+                })
+                .into_response(),
+                (Ok(_), None) => (StatusCode::BAD_REQUEST).into_response(),
+                (Err(error), _) => storage_error(error),
             }
         }
     }
 }
 
+/// Score a draft competition and show how the ranking would change with it,
+/// nothing is saved
+async fn simulate_competition(
+    State(state): State<AppState>,
+    Json(request): extract::Json<SimulationRequest>,
+) -> Response {
+    if let Err(error) = request.competition.validate() {
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    let comp_date = match request.competition.comp_date.parse::<NaiveDate>() {
+        Ok(date) => date,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+    };
+    let ranking_date = match request.ranking_date {
+        Some(date) => date.parse::<NaiveDate>().ok(),
+        None => ranking_month(comp_date).checked_add_months(Months::new(1)),
+    };
+    let ranking_date = match ranking_date {
+        Some(date) if date > comp_date => date,
+        Some(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "The ranking date must be after the competition",
+            )
+                .into_response()
+        }
+        None => return (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+    };
+    let (rankings, competitions) = match (state.storage.rankings(), state.storage.competitions()) {
+        (Ok(rankings), Ok(competitions)) => (rankings, competitions),
+        (Err(error), _) | (_, Err(error)) => return storage_error(error),
+    };
+    match simulation::simulate_competition(
+        &request.competition,
+        &ranking_date,
+        &competitions,
+        &rankings,
+        &state.scoring,
+    ) {
+        Some(result) => Json(result).into_response(),
+        None => (StatusCode::BAD_REQUEST).into_response(),
+    }
+}
+
+/// The value a planned competition can reach with its entry list and what
+/// each top ranked pilot not yet entered would add
+async fn forecast_competition(
+    State(state): State<AppState>,
+    Json(request): extract::Json<ForecastRequest>,
+) -> Response {
+    if request.comp_date.parse::<NaiveDate>().is_err() {
+        return (StatusCode::BAD_REQUEST, "Not a valid date").into_response();
+    }
+    if request.num_tasks < 1 || request.pins.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "A forecast needs at least one task and one pilot",
+        )
+            .into_response();
+    }
+    let (rankings, competitions) = match (state.storage.rankings(), state.storage.competitions()) {
+        (Ok(rankings), Ok(competitions)) => (rankings, competitions),
+        (Err(error), _) | (_, Err(error)) => return storage_error(error),
+    };
+    match scoring::forecast_competition(&request, &competitions, &rankings, &state.scoring) {
+        Some(forecast) => Json(forecast).into_response(),
+        None => (StatusCode::BAD_REQUEST).into_response(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use frontend::prs_data_types::{CompetitionPilot, Placing};
+    use axum::body::HttpBody;
+    use frontend::prs_data_types::{CompetitionKind, CompetitionPilot, Discipline, Placing, Root};
     use serde_json::json;
 
     use crate::data::data_access::load_data;
@@ -70,7 +276,7 @@ mod tests {
     #[tokio::test]
     async fn competition_should_return_result() {
         let result = competition(
-            State(load_data().unwrap()),
+            State(load_data().unwrap().into()),
             Path("2020-03-01-Rotorua".to_string()),
         )
         .await;
@@ -79,19 +285,29 @@ mod tests {
 
     #[tokio::test]
     async fn competitions_should_return_result() {
-        let result = competitions(State(load_data().unwrap())).await;
+        let result = competitions(State(load_data().unwrap().into())).await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn exchange_rate_should_return_result() {
+        let result = exchange_rate(
+            State(load_data().unwrap().into()),
+            Path("2023-01-01".to_string()),
+            Query(DisciplineQuery::default()),
+        )
+        .await;
         assert_eq!(result.status(), StatusCode::OK);
     }
 
     #[tokio::test]
     async fn create_valid_competition_should_return_result() {
         let result = create_competition(
-            State(load_data().unwrap()),
+            State(load_data().unwrap().into()),
             Json(Competition {
                 id: "NewComp".to_string(),
                 name: "NewComp".to_string(),
                 location: "NewLocation".to_string(),
-                overseas: false,
                 exchange_rate: 1.0,
                 comp_date: "2022-01-01".to_string(),
                 comp_value: 0.0,
@@ -114,22 +330,206 @@ mod tests {
                     fai_points: 0.0,
                     pp: 0.0,
                     pplacing: 0.0,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn created_competition_should_be_returned() {
+        let state: AppState = Root::default().into();
+        let result = create_competition(
+            State(state.clone()),
+            Json(Competition {
+                name: "NewComp".to_string(),
+                location: "NewLocation".to_string(),
+                comp_date: "2022-01-01".to_string(),
+                num_tasks: 2,
+                pq: json!(0.0),
+                placings: vec![Placing {
+                    id: 1,
+                    pilot: CompetitionPilot {
+                        pin: "5410".to_owned(),
+                        ..Default::default()
+                    },
+                    place: 1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = competition(
+            State(state.clone()),
+            Path("2022-01-01-NewLocation".to_string()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(state.storage.competitions().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn back_dated_competition_should_update_later_rankings() {
+        let state: AppState = load_data().unwrap().into();
+        let ranking_date = state
+            .storage
+            .rankings()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.date)
+            .max()
+            .unwrap();
+        let comp_date = ranking_date
+            .parse::<NaiveDate>()
+            .unwrap()
+            .checked_sub_months(Months::new(2))
+            .unwrap();
+        let total = |state: &AppState| {
+            state
+                .storage
+                .ranking(&ranking_date, Discipline::ParaglidingXc)
+                .unwrap()
+                .unwrap()
+                .ranking_points
+                .iter()
+                .find(|p| p.pilot_pin == "99999")
+                .map_or(0.0, |p| p.total_points)
+        };
+        let before = total(&state);
+        let result = create_competition(
+            State(state.clone()),
+            Json(Competition {
+                name: "Back dated".to_string(),
+                location: "Wanaka".to_string(),
+                comp_date: comp_date.format("%Y-%m-%d").to_string(),
+                num_tasks: 3,
+                pq: json!(0.0),
+                placings: vec![Placing {
+                    pilot: CompetitionPilot {
+                        pin: "99999".to_owned(),
+                        ..Default::default()
+                    },
+                    place: 1,
+                    ..Default::default()
                 }],
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(total(&state) > before);
+        let created: CreatedCompetition = response_json(result).await;
+        assert_eq!(created.competition.name, "Back dated");
+        assert!(created.recalculation.applied);
+        assert!(created
+            .recalculation
+            .rankings
+            .iter()
+            .any(|r| r.date == ranking_date && r.discipline == Discipline::ParaglidingXc));
+    }
+
+    async fn response_json<T: serde::de::DeserializeOwned>(response: Response) -> T {
+        let mut body = response.into_body();
+        let mut bytes = vec![];
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn simulated_competition_should_not_be_saved() {
+        let state: AppState = Root::default().into();
+        let draft = Competition {
+            name: "Draft".to_string(),
+            location: "Wanaka".to_string(),
+            comp_date: "2022-01-10".to_string(),
+            num_tasks: 2,
+            pq: json!(0.0),
+            placings: vec![Placing {
+                pilot: CompetitionPilot {
+                    pin: "5410".to_owned(),
+                    ..Default::default()
+                },
+                place: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let result = simulate_competition(
+            State(state.clone()),
+            Json(SimulationRequest {
+                competition: draft.clone(),
+                ranking_date: None,
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(state.storage.competitions().unwrap().is_empty());
+        let result = simulate_competition(
+            State(state),
+            Json(SimulationRequest {
+                competition: draft,
+                ranking_date: Some("2022-01-01".to_string()),
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn task_quality_should_return_result() {
+        let result = task_quality(
+            State(Root::default().into()),
+            Path("2023-01-01".to_string()),
+            Query(TaskQualityQuery {
+                num_tasks: 3,
+                total_validity: Some(2.5),
+                ..Default::default()
             }),
         )
         .await;
         assert_eq!(result.status(), StatusCode::OK);
+        let result = task_quality(
+            State(Root::default().into()),
+            Path("2023-13-01".to_string()),
+            Query(TaskQualityQuery::default()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn forecast_should_return_result() {
+        let request = ForecastRequest {
+            comp_date: "2023-01-10".to_string(),
+            num_tasks: 4,
+            pins: vec!["5410".to_string()],
+            ..Default::default()
+        };
+        let result = forecast_competition(State(load_data().unwrap().into()), Json(request)).await;
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = forecast_competition(
+            State(load_data().unwrap().into()),
+            Json(ForecastRequest::default()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
     async fn create_invalid_competition_should_return_badrequest() {
         let result = create_competition(
-            State(load_data().unwrap()),
+            State(load_data().unwrap().into()),
             Json(Competition {
                 id: "1".to_string(),
                 name: "1".to_string(),
                 location: "NewLocation".to_string(),
-                overseas: false,
                 exchange_rate: 1.0,
                 comp_date: "2022".to_string(),
                 comp_value: 0.0,
@@ -152,7 +552,32 @@ mod tests {
                     fai_points: 0.0,
                     pp: 0.0,
                     pplacing: 0.0,
+                    ..Default::default()
                 }],
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn league_with_too_few_pilots_should_return_badrequest() {
+        let result = create_competition(
+            State(Root::default().into()),
+            Json(Competition {
+                name: "Overseas league".to_string(),
+                location: "Overseas".to_string(),
+                comp_date: "2022-01-01".to_string(),
+                num_tasks: 2,
+                kind: CompetitionKind::OverseasLeague,
+                placings: (1..6)
+                    .map(|place| Placing {
+                        place,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
             }),
         )
         .await;
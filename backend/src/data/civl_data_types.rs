@@ -0,0 +1,337 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use frontend::prs_data_types::ImportRowError;
+use scraper::{ElementRef, Html, Selector};
+
+/// Headers of the pilot columns in the competition's WPRS results
+const RANK_HEADER: &str = "Rank";
+const POINTS_HEADER: &str = "Points";
+const NAME_HEADER: &str = "Pilot";
+const NATION_HEADER: &str = "Nation";
+const CIVL_ID_HEADER: &str = "CIVL ID";
+
+/// A competition's page on civlcomps.org
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CivlCompetition {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub category: String,
+    pub country: String,
+    pub num_pilots: usize,
+    pub pq: f64,
+    pub pn: f64,
+    pub ta: f64,
+    pub pilots: Vec<CivlPilot>,
+}
+
+/// Where each of a pilot's details is in the rows of the WPRS results
+struct PilotColumns {
+    rank: usize,
+    points: usize,
+    name: usize,
+    nation: usize,
+    civl_id: usize,
+}
+
+/// A pilot's row in a competition's WPRS results
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CivlPilot {
+    pub civl_id: String,
+    pub name: String,
+    /// Three letter code, NZL for New Zealand
+    pub nation: String,
+    /// As written, may be a tie or a status like "DNF"
+    pub rank: String,
+    pub wprs_points: f64,
+}
+
+impl CivlCompetition {
+    /// Read the competition from its page. Fails naming what is missing when
+    /// the page isn't laid out as expected, pilot rows that can't be read are
+    /// left out and reported
+    pub fn parse(html: &Html) -> Result<(CivlCompetition, Vec<ImportRowError>)> {
+        let table_data = selector("td")?;
+        let name = html
+            .select(&selector(".header-rankings h2")?)
+            .next()
+            .map(|header| cell_text(&header))
+            .filter(|name| !name.is_empty())
+            .ok_or(anyhow!("No competition name in .header-rankings h2"))?;
+
+        let headers: Vec<String> = html
+            .select(&selector("#tableMain>thead>tr>th")?)
+            .map(|header| cell_text(&header))
+            .collect();
+        let cells: Vec<ElementRef> = html
+            .select(&selector("#tableMain>tbody>tr")?)
+            .next()
+            .ok_or(anyhow!("No competition details in #tableMain"))?
+            .select(&table_data)
+            .collect();
+        // The first cell has the start and end dates on separate lines
+        let dates: Vec<String> = cells
+            .first()
+            .map(|cell| {
+                cell.text()
+                    .map(|text| text.trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let start_date = dates
+            .first()
+            .and_then(|date| parse_date(date))
+            .ok_or(anyhow!("No start date in {:?}", dates))?;
+        let end_date = dates.get(1).and_then(|date| parse_date(date));
+        let detail = |label: &str| -> Result<String> {
+            headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(label))
+                .and_then(|column| cells.get(column))
+                .map(cell_text)
+                .ok_or(anyhow!("No {} column in #tableMain", label))
+        };
+        let figure = |label: &str| -> Result<f64> {
+            let text = detail(label)?;
+            text.parse::<f64>()
+                .map_err(|_| anyhow!("{} {:?} is not a number", label, text))
+        };
+
+        // The pilots' table is the innermost one with pilot rows
+        let pilot_item = selector(".pilot-item")?;
+        let results_table = html
+            .select(&selector("table")?)
+            .filter(|table| table.select(&pilot_item).next().is_some())
+            .last();
+        let mut errors = vec![];
+        let pilots = match results_table {
+            None => vec![],
+            Some(table) => {
+                let headers: Vec<String> = table
+                    .select(&selector("thead th")?)
+                    .map(|header| cell_text(&header))
+                    .collect();
+                let columns = PilotColumns::find(&headers)?;
+                table
+                    .select(&pilot_item)
+                    .enumerate()
+                    .filter_map(|(row, item)| {
+                        let cells: Vec<String> =
+                            item.select(&table_data).map(|c| cell_text(&c)).collect();
+                        match parse_pilot(&cells, &columns) {
+                            Ok(pilot) => Some(pilot),
+                            Err(error) => {
+                                errors.push(ImportRowError {
+                                    row,
+                                    message: error.to_string(),
+                                });
+                                None
+                            }
+                        }
+                    })
+                    .collect()
+            }
+        };
+
+        let competition = CivlCompetition {
+            name,
+            start_date,
+            end_date,
+            category: detail("Category")?,
+            country: detail("Country")?,
+            num_pilots: figure("Pilots")? as usize,
+            pq: figure("Pq")?,
+            pn: figure("Pn")?,
+            ta: figure("Ta")?,
+            pilots,
+        };
+        Ok((competition, errors))
+    }
+}
+
+impl PilotColumns {
+    /// Find the columns by the results table's headers, failing naming the
+    /// first one missing
+    fn find(headers: &[String]) -> Result<PilotColumns> {
+        let column = |label: &str| {
+            headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(label))
+                .ok_or(anyhow!("No {} column in the pilot results", label))
+        };
+        Ok(PilotColumns {
+            rank: column(RANK_HEADER)?,
+            points: column(POINTS_HEADER)?,
+            name: column(NAME_HEADER)?,
+            nation: column(NATION_HEADER)?,
+            civl_id: column(CIVL_ID_HEADER)?,
+        })
+    }
+}
+
+fn parse_pilot(cells: &[String], columns: &PilotColumns) -> Result<CivlPilot> {
+    let cell = |index: usize, label: &str| {
+        cells
+            .get(index)
+            .cloned()
+            .ok_or(anyhow!("No {} in a row of {} cells", label, cells.len()))
+    };
+    let points = cell(columns.points, "WPRS points")?;
+    Ok(CivlPilot {
+        rank: cell(columns.rank, "rank")?,
+        wprs_points: points
+            .parse::<f64>()
+            .map_err(|_| anyhow!("WPRS points {:?} is not a number", points))?,
+        name: cell(columns.name, "name")?,
+        nation: cell(columns.nation, "nation")?,
+        civl_id: cell(columns.civl_id, "CIVL id")?,
+    })
+}
+
+fn selector(selectors: &str) -> Result<Selector> {
+    Selector::parse(selectors).map_err(|_| anyhow!("Invalid selector {}", selectors))
+}
+
+/// An element's text without its markup
+fn cell_text(element: &ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+/// Read a date as written on results pages
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%d %b %Y", "%d %B %Y", "%d.%m.%Y", "%d/%m/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date.trim(), format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PILOT_HEADERS: &str = "<th>Rank</th><th></th><th>Points</th><th></th><th></th><th>Pilot</th><th>Nation</th><th>CIVL ID</th>";
+
+    fn page(details: &str, pilots: &str) -> Html {
+        results_page(details, PILOT_HEADERS, pilots)
+    }
+
+    fn results_page(details: &str, pilot_headers: &str, pilots: &str) -> Html {
+        Html::parse_document(&format!(
+            r#"<html><body>
+            <div class="header-rankings"><h2>Bright Open 2023</h2></div>
+            <table id="tableMain">
+              <thead><tr><th>Period</th><th>Category</th><th>Country</th><th>Pilots</th><th>Pq</th><th>Pn</th><th>Ta</th><th>Td</th></tr></thead>
+              <tbody><tr>{}</tr></tbody>
+            </table>
+            <table><thead><tr>{}</tr></thead><tbody>{}</tbody></table>
+            </body></html>"#,
+            details, pilot_headers, pilots
+        ))
+    }
+
+    const DETAILS: &str = "<td>2023-01-05<br>2023-01-12</td><td>2</td><td>Australia</td><td>120</td><td>0.78</td><td>1.1</td><td>1</td><td>1</td>";
+
+    #[test]
+    fn parse_should_read_the_competition() {
+        let html = page(
+            DETAILS,
+            r#"<tr class="pilot-item"><td>1</td><td></td><td>95.5</td><td></td><td></td><td><a href="/pilot/1">Ann Smith</a></td><td>NZL</td><td>12345</td></tr>
+               <tr class="pilot-item"><td>=2</td><td></td><td>90</td><td></td><td></td><td>Bob Jones</td><td>AUS</td><td>67890</td></tr>"#,
+        );
+        let (competition, errors) = CivlCompetition::parse(&html).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(competition.name, "Bright Open 2023");
+        assert_eq!(
+            competition.start_date,
+            NaiveDate::from_ymd_opt(2023, 1, 5).unwrap()
+        );
+        assert_eq!(competition.end_date, NaiveDate::from_ymd_opt(2023, 1, 12));
+        assert_eq!(competition.category, "2");
+        assert_eq!(competition.country, "Australia");
+        assert_eq!(competition.num_pilots, 120);
+        assert_eq!(competition.pq, 0.78);
+        assert_eq!(competition.pn, 1.1);
+        assert_eq!(competition.ta, 1.0);
+        assert_eq!(
+            competition.pilots[0],
+            CivlPilot {
+                civl_id: "12345".to_string(),
+                name: "Ann Smith".to_string(),
+                nation: "NZL".to_string(),
+                rank: "1".to_string(),
+                wprs_points: 95.5,
+            }
+        );
+        assert_eq!(competition.pilots[1].rank, "=2");
+    }
+
+    #[test]
+    fn parse_should_report_layout_changes() {
+        let error = CivlCompetition::parse(&Html::parse_document("<html></html>")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No competition name in .header-rankings h2"
+        );
+        let error = CivlCompetition::parse(&page(
+            "<td>2023-01-05</td><td>2</td><td>Australia</td><td>many</td>",
+            "",
+        ))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Pilots \"many\" is not a number");
+
+        let (competition, errors) = CivlCompetition::parse(&page(
+            DETAILS,
+            r#"<tr class="pilot-item"><td>1</td><td></td><td>-</td></tr>
+               <tr class="pilot-item"><td>2</td></tr>"#,
+        ))
+        .unwrap();
+        assert!(competition.pilots.is_empty());
+        assert_eq!(competition.end_date, NaiveDate::from_ymd_opt(2023, 1, 12));
+        assert_eq!(
+            errors,
+            vec![
+                ImportRowError {
+                    row: 0,
+                    message: "WPRS points \"-\" is not a number".to_string()
+                },
+                ImportRowError {
+                    row: 1,
+                    message: "No WPRS points in a row of 1 cells".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_should_find_pilot_columns_by_header() {
+        let pilots = r#"<tr class="pilot-item"><td>12345</td><td>NZL</td><td>Ann Smith</td><td>95.5</td><td>1</td></tr>"#;
+        let (competition, errors) = CivlCompetition::parse(&results_page(
+            DETAILS,
+            "<th>CIVL ID</th><th>Nation</th><th>Pilot</th><th>Points</th><th>Rank</th>",
+            pilots,
+        ))
+        .unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(competition.pilots[0].civl_id, "12345");
+        assert_eq!(competition.pilots[0].name, "Ann Smith");
+        assert_eq!(competition.pilots[0].wprs_points, 95.5);
+        assert_eq!(competition.pilots[0].rank, "1");
+
+        let error = CivlCompetition::parse(&results_page(
+            DETAILS,
+            "<th>CIVL ID</th><th>Country</th><th>Pilot</th><th>Points</th><th>Rank</th>",
+            pilots,
+        ))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "No Nation column in the pilot results");
+    }
+
+    #[test]
+    fn parse_date_should_read_results_page_dates() {
+        let date = NaiveDate::from_ymd_opt(2023, 1, 5);
+        assert_eq!(parse_date("2023-01-05"), date);
+        assert_eq!(parse_date(" 05 Jan 2023 "), date);
+        assert_eq!(parse_date("05.01.2023"), date);
+        assert_eq!(parse_date("soon"), None);
+    }
+}
@@ -1,39 +1,46 @@
 use anyhow::Result;
 use frontend::prs_data_types;
-use scraper::Html;
 use serde_json::from_str;
-use std::fs;
+use std::{fs, io::Write, path::Path};
+
+pub const DATA_PATH: &str = "./data/nzprsBackup.json";
 
 pub fn load_data() -> Result<prs_data_types::Root> {
-    let contents: String = fs::read_to_string("./data/nzprsBackup.json")?;
+    load_data_from(Path::new(DATA_PATH))
+}
+
+pub fn load_data_from(path: &Path) -> Result<prs_data_types::Root> {
+    let contents: String = fs::read_to_string(path)?;
     let r = from_str(&contents)?;
     Ok(r)
 }
 
-pub async fn get_data_external<T>(path: String) -> Result<T, MultiError>
-where
-    T: serde::de::DeserializeOwned,
-{
-    let response = reqwest::get(path).await;
-    match response {
-        Err(_) => Err(MultiError::RequestError),
-        Ok(response) => match response.text().await {
-            Err(_) => Err(MultiError::RequestError),
-            Ok(text) => match serde_json::from_str::<T>(&text) {
-                Err(_) => Err(MultiError::DeserializeError),
-                Ok(result) => Ok(result),
-            },
-        },
+/// Write the data file, keeping the previous version alongside it as a backup.
+/// The new contents go to a temporary file first and are renamed into place so
+/// a failed write can never leave a truncated data file behind.
+pub fn save_data(data: &prs_data_types::Root, path: &Path) -> Result<()> {
+    let contents = serde_json::to_string_pretty(data)?;
+    let temp_path = path.with_extension("json.tmp");
+    {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    if path.exists() {
+        fs::copy(path, path.with_extension("json.bak"))?;
     }
+    fs::rename(&temp_path, path)?;
+    Ok(())
 }
 
-pub async fn get_html_external(path: String) -> Result<Html, MultiError> {
+/// Fetch a page or API response as text for an importer to read
+pub async fn get_text_external(path: String) -> Result<String, MultiError> {
     let response = reqwest::get(path).await;
     match response {
         Err(_) => Err(MultiError::RequestError),
         Ok(response) => match response.text().await {
             Err(_) => Err(MultiError::RequestError),
-            Ok(text) => Ok(Html::parse_document(&text)),
+            Ok(text) => Ok(text),
         },
     }
 }
@@ -41,6 +48,36 @@ pub async fn get_html_external(path: String) -> Result<Html, MultiError> {
 #[derive(Clone, Debug, PartialEq)]
 pub enum MultiError {
     RequestError,
-    DeserializeError,
     // etc.
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frontend::prs_data_types::{Pilot, Root};
+
+    #[test]
+    fn save_data_should_keep_backup() -> Result<()> {
+        let dir = std::env::temp_dir().join("nzprs_save_data_should_keep_backup");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("nzprsBackup.json");
+        let mut root = Root::default();
+        save_data(&root, &path)?;
+        root.pilots.push(Pilot {
+            pin: "1001".to_string(),
+            ..Default::default()
+        });
+        save_data(&root, &path)?;
+
+        assert_eq!(load_data_from(&path)?, root);
+        assert_eq!(
+            load_data_from(&path.with_extension("json.bak"))?
+                .pilots
+                .len(),
+            0
+        );
+        assert_eq!(path.with_extension("json.tmp").exists(), false);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+/// A competition as saved by FS, the FAI scoring program, in its .fsdb file
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FsdbCompetition {
+    pub name: String,
+    pub location: String,
+    pub from: String,
+    pub to: String,
+    pub tasks: Vec<FsdbTask>,
+    pub participants: Vec<FsdbParticipant>,
+    /// The published results, one for each class such as overall or women
+    pub results: Vec<FsdbResults>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FsdbTask {
+    pub id: String,
+    pub name: String,
+    /// FS's day quality, 0 to 1
+    pub validity: Option<f64>,
+    pub cancelled: bool,
+    /// Each participant's points on the task by participant id
+    pub points: HashMap<String, f64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FsdbParticipant {
+    /// FS's id for the participant within the competition
+    pub id: String,
+    pub name: String,
+    pub civl_id: String,
+    pub nation: String,
+    pub gender: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FsdbResults {
+    pub title: String,
+    pub placings: Vec<FsdbPlacing>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FsdbPlacing {
+    pub participant_id: String,
+    pub rank: String,
+    pub points: Option<f64>,
+}
+
+impl FsdbCompetition {
+    /// Read the competition from the file's contents, failing when it isn't
+    /// an FSDB file
+    pub fn parse(xml: &str) -> Result<FsdbCompetition> {
+        let root = read_xml(xml)?;
+        let competition = root.child("FsCompetition").ok_or(anyhow!(
+            "No FsCompetition in the file, is it an .fsdb file?"
+        ))?;
+        let number = |element: &Element, name: &str| {
+            element
+                .attribute(name)
+                .and_then(|value| value.trim().parse::<f64>().ok())
+        };
+        let participants = competition
+            .children_named("FsParticipants")
+            .flat_map(|participants| participants.children_named("FsParticipant"))
+            .map(|participant| FsdbParticipant {
+                id: participant.text_attribute("id"),
+                name: participant.text_attribute("name"),
+                civl_id: participant.text_attribute("CIVLID"),
+                nation: participant.text_attribute("nat_code_3166_a3"),
+                gender: match participant.attribute("female") {
+                    Some("1") => "F".to_string(),
+                    Some(_) => "M".to_string(),
+                    None => String::new(),
+                },
+            })
+            .collect();
+        let tasks = competition
+            .children_named("FsTasks")
+            .flat_map(|tasks| tasks.children_named("FsTask"))
+            .map(|task| FsdbTask {
+                id: task.text_attribute("id"),
+                name: task.text_attribute("name"),
+                validity: task
+                    .child("FsTaskScoreParams")
+                    .and_then(|params| number(params, "day_quality")),
+                cancelled: task
+                    .child("FsTaskState")
+                    .and_then(|state| state.attribute("task_state"))
+                    .is_some_and(|state| state.eq_ignore_ascii_case("cancelled")),
+                points: task
+                    .children_named("FsParticipants")
+                    .flat_map(|participants| participants.children_named("FsParticipant"))
+                    .filter_map(|participant| {
+                        let points = participant
+                            .child("FsResult")
+                            .and_then(|result| number(result, "points"))?;
+                        Some((participant.text_attribute("id"), points))
+                    })
+                    .collect(),
+            })
+            .collect();
+        let results = competition
+            .children_named("FsCompetitionResults")
+            .flat_map(|results| results.children_named("FsCompetitionResult"))
+            .map(|result| FsdbResults {
+                title: result.text_attribute("title"),
+                placings: result
+                    .children_named("FsParticipant")
+                    .map(|placing| FsdbPlacing {
+                        participant_id: placing.text_attribute("id"),
+                        rank: placing.text_attribute("rank"),
+                        points: number(placing, "points"),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(FsdbCompetition {
+            name: competition.text_attribute("name"),
+            location: competition.text_attribute("location"),
+            from: competition.text_attribute("from"),
+            to: competition.text_attribute("to"),
+            tasks,
+            participants,
+            results,
+        })
+    }
+
+    /// The overall results, or the first class when none is titled overall
+    pub fn overall_results(&self) -> Option<&FsdbResults> {
+        self.results
+            .iter()
+            .find(|results| results.title.eq_ignore_ascii_case("overall"))
+            .or(self.results.first())
+    }
+
+    pub fn participant(&self, id: &str) -> Option<&FsdbParticipant> {
+        self.participants.iter().find(|p| p.id == id)
+    }
+}
+
+/// An XML element, FSDB keeps everything in attributes so text is skipped
+#[derive(Default, Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn text_attribute(&self, name: &str) -> String {
+        self.attribute(name).unwrap_or_default().trim().to_string()
+    }
+
+    fn child<'a>(&'a self, name: &'a str) -> Option<&'a Element> {
+        self.children_named(name).next()
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+/// Read an XML document into its root element
+fn read_xml(xml: &str) -> Result<Element> {
+    // The document itself is at the bottom of the stack
+    let mut stack = vec![Element::default()];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        // Declarations, comments and CDATA have nothing FSDB needs
+        if let Some((open, close)) = [
+            ("<?", "?>"),
+            ("<!--", "-->"),
+            ("<![CDATA[", "]]>"),
+            ("<!", ">"),
+        ]
+        .into_iter()
+        .find(|(open, _)| rest.starts_with(open))
+        {
+            let end = rest
+                .find(close)
+                .ok_or(anyhow!("{} is never closed", open))?;
+            rest = &rest[end + close.len()..];
+            continue;
+        }
+        if let Some(tag) = rest.strip_prefix("</") {
+            let end = tag.find('>').ok_or(anyhow!("Unfinished end tag"))?;
+            let name = tag[..end].trim();
+            let element = match stack.pop() {
+                Some(element) if !stack.is_empty() && element.name == name => element,
+                Some(element) if !stack.is_empty() => {
+                    return Err(anyhow!("</{}> where <{}> should close", name, element.name))
+                }
+                _ => return Err(anyhow!("</{}> has no open element", name)),
+            };
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(element);
+            }
+            rest = &tag[end + 1..];
+            continue;
+        }
+        let (element, self_closing, remaining) = read_tag(&rest[1..])?;
+        rest = remaining;
+        match self_closing {
+            true => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            false => stack.push(element),
+        }
+    }
+    match stack.pop() {
+        Some(document) if stack.is_empty() => document
+            .children
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("The file has no elements")),
+        Some(element) => Err(anyhow!("<{}> is never closed", element.name)),
+        None => Err(anyhow!("The file has no elements")),
+    }
+}
+
+/// Read a start tag after its '<', returning whether it closes itself and
+/// what follows it
+fn read_tag(tag: &str) -> Result<(Element, bool, &str)> {
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .ok_or(anyhow!("Unfinished tag"))?;
+    let mut element = Element {
+        name: tag[..name_end].to_string(),
+        ..Default::default()
+    };
+    let mut rest = &tag[name_end..];
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return Ok((element, true, after));
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return Ok((element, false, after));
+        }
+        let equals = rest
+            .find('=')
+            .ok_or(anyhow!("Unfinished attribute in <{}>", element.name))?;
+        let name = rest[..equals].trim().to_string();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or(anyhow!("Unquoted {} in <{}>", name, element.name))?;
+        let value_end =
+            value[1..]
+                .find(quote)
+                .ok_or(anyhow!("Unfinished {} in <{}>", name, element.name))?;
+        element
+            .attributes
+            .push((name, decode(&value[1..value_end + 1])));
+        rest = &value[value_end + 2..];
+    }
+}
+
+/// Replace the XML entities in an attribute's value
+fn decode(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let character = entity.and_then(|(entity, _)| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|code| code.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (character, entity) {
+            (Some(character), Some((_, end))) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// A small FSDB file with a cancelled task, a pilot who didn't fly and two
+/// classes of results
+#[cfg(test)]
+pub const EXAMPLE_FSDB: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Fs version="3.5">
+  <!-- exported by FS -->
+  <FsCompetition id="1" name="NZ Nationals &amp; Open" location="Ōmarama" from="2023-01-05" to="2023-01-12">
+    <FsParticipants>
+      <FsParticipant id="1" name="Ann Smith" nat_code_3166_a3="NZL" female="1" CIVLID="12345" />
+      <FsParticipant id="2" name="Bob Jones" nat_code_3166_a3="AUS" female="0" CIVLID="67890">
+        <FsCustomAttributes><FsCustomAttribute name="club" value="Canterbury"/></FsCustomAttributes>
+      </FsParticipant>
+    </FsParticipants>
+    <FsTasks>
+      <FsTask id="1" name="Task 1">
+        <FsTaskState task_state="REGULAR" />
+        <FsTaskScoreParams day_quality="0.95" />
+        <FsParticipants>
+          <FsParticipant id="1"><FsFlightData distance="40.1" /><FsResult rank="1" points="950" /></FsParticipant>
+          <FsParticipant id="2" />
+        </FsParticipants>
+      </FsTask>
+      <FsTask id="2" name="Task 2">
+        <FsTaskState task_state="CANCELLED" />
+      </FsTask>
+    </FsTasks>
+    <FsCompetitionResults>
+      <FsCompetitionResult title="Women" top="all">
+        <FsParticipant id="1" rank="1" points="950" />
+      </FsCompetitionResult>
+      <FsCompetitionResult title="Overall" top="all">
+        <FsParticipant id="1" rank="1" points="950" />
+        <FsParticipant id="2" rank="2" points="0" />
+      </FsCompetitionResult>
+    </FsCompetitionResults>
+  </FsCompetition>
+</Fs>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_read_the_competition() {
+        let competition = FsdbCompetition::parse(EXAMPLE_FSDB).unwrap();
+        assert_eq!(competition.name, "NZ Nationals & Open");
+        assert_eq!(competition.location, "Ōmarama");
+        assert_eq!(competition.from, "2023-01-05");
+        assert_eq!(
+            competition.participants[0],
+            FsdbParticipant {
+                id: "1".to_string(),
+                name: "Ann Smith".to_string(),
+                civl_id: "12345".to_string(),
+                nation: "NZL".to_string(),
+                gender: "F".to_string(),
+            }
+        );
+        assert_eq!(competition.participants[1].gender, "M");
+        assert_eq!(competition.tasks.len(), 2);
+        assert_eq!(competition.tasks[0].validity, Some(0.95));
+        assert_eq!(competition.tasks[0].points.get("1"), Some(&950.0));
+        assert_eq!(competition.tasks[0].points.get("2"), None);
+        assert!(competition.tasks[1].cancelled);
+        let overall = competition.overall_results().unwrap();
+        assert_eq!(overall.title, "Overall");
+        assert_eq!(overall.placings.len(), 2);
+        assert_eq!(overall.placings[1].points, Some(0.0));
+    }
+
+    #[test]
+    fn parse_should_fail_on_other_files() {
+        let error = FsdbCompetition::parse("<html><body></body></html>").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No FsCompetition in the file, is it an .fsdb file?"
+        );
+        let error = FsdbCompetition::parse("<Fs><FsCompetition></Fs>").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "</Fs> where <FsCompetition> should close"
+        );
+        let error = FsdbCompetition::parse("<Fs><FsCompetition name=\"x></Fs>").unwrap_err();
+        assert_eq!(error.to_string(), "Unfinished name in <FsCompetition>");
+        assert!(FsdbCompetition::parse("").is_err());
+    }
+
+    #[test]
+    fn decode_should_replace_entities() {
+        assert_eq!(
+            decode("a &amp; b &lt;&#65;&#x42;&gt; &bogus; &"),
+            "a & b <AB> &bogus; &"
+        );
+    }
+}
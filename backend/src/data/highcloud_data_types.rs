@@ -1,6 +1,17 @@
+use frontend::prs_data_types::ImportRowError;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Columns of a `get_result.php` results row, the task scores follow the total
+const RANK_COLUMN: usize = 0;
+const PIN_COLUMN: usize = 1;
+const NAME_COLUMN: usize = 3;
+const NATION_COLUMN: usize = 4;
+const GENDER_COLUMN: usize = 5;
+const GLIDER_COLUMN: usize = 7;
+const TOTAL_COLUMN: usize = 9;
+const FIRST_TASK_COLUMN: usize = 10;
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HighCloudRoot {
@@ -66,3 +77,193 @@ pub struct Compinfo {
     #[serde(rename = "TotalValidity")]
     pub total_validity: i64,
 }
+
+/// A pilot's row from HighCloud's results
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct HighCloudResult {
+    /// The row of HighCloud's results it was read from
+    pub row: usize,
+    /// As written, may be a tie like "=2", blank or a status like "DNF"
+    pub rank: String,
+    pub pin: String,
+    pub name: String,
+    pub nation: String,
+    pub gender: String,
+    pub glider: String,
+    /// None where the pilot has no score on the task
+    pub task_scores: Vec<Option<f64>>,
+    /// HighCloud's total, or the sum of the task scores when it doesn't give
+    /// one. None when the pilot has no scores at all
+    pub total: Option<f64>,
+}
+
+impl HighCloudRoot {
+    /// The number of tasks, up to the last task cell filled in on any row so
+    /// a task a pilot has no score on is still counted
+    pub fn task_count(&self) -> usize {
+        self.data
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .skip(FIRST_TASK_COLUMN)
+                    .rposition(|cell| number(cell) != Ok(None))
+                    .map_or(0, |last| last + 1)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Read every row. Rows without a pilot's name are left out and a score
+    /// that isn't a number is read as no score, both are reported as errors
+    pub fn results(&self) -> (Vec<HighCloudResult>, Vec<ImportRowError>) {
+        let tasks = self.task_count();
+        let mut results = vec![];
+        let mut errors = vec![];
+        for (index, row) in self.data.iter().enumerate() {
+            let mut error = |message: String| {
+                errors.push(ImportRowError {
+                    row: index,
+                    message,
+                })
+            };
+            let name = text(row, NAME_COLUMN);
+            if name.is_empty() {
+                error("No pilot name".to_string());
+                continue;
+            }
+            let task_scores: Vec<Option<f64>> = (0..tasks)
+                .map(|task| {
+                    let cell = row.get(FIRST_TASK_COLUMN + task).unwrap_or(&Value::Null);
+                    number(cell).unwrap_or_else(|_| {
+                        error(format!("Task {} score {} is not a number", task + 1, cell));
+                        None
+                    })
+                })
+                .collect();
+            let scored: Vec<f64> = task_scores.iter().flatten().copied().collect();
+            let total = match row.get(TOTAL_COLUMN).map(number) {
+                Some(Ok(Some(total))) => Some(total),
+                _ if scored.is_empty() => None,
+                _ => Some(scored.iter().sum()),
+            };
+            results.push(HighCloudResult {
+                row: index,
+                rank: text(row, RANK_COLUMN),
+                pin: text(row, PIN_COLUMN),
+                name,
+                nation: text(row, NATION_COLUMN),
+                gender: text(row, GENDER_COLUMN),
+                glider: text(row, GLIDER_COLUMN),
+                task_scores,
+                total,
+            });
+        }
+        (results, errors)
+    }
+}
+
+/// A cell as text, empty when it is missing or not text or a number
+fn text(row: &[Value], column: usize) -> String {
+    match row.get(column) {
+        Some(Value::String(text)) => text.trim().to_string(),
+        Some(Value::Number(number)) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// A cell as a number, None when it is empty and an error when it is
+/// something else
+fn number(cell: &Value) -> Result<Option<f64>, ()> {
+    match cell {
+        Value::Null => Ok(None),
+        Value::Number(number) => Ok(number.as_f64()),
+        Value::String(text) if text.trim().is_empty() => Ok(None),
+        Value::String(text) => text.trim().parse::<f64>().map(Some).map_err(|_| ()),
+        _ => Err(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows as HighCloud writes them, a row that isn't a list reads as empty
+    fn root(data: &str) -> HighCloudRoot {
+        let rows: Vec<Value> = serde_json::from_str(data).unwrap();
+        HighCloudRoot {
+            data: rows
+                .into_iter()
+                .map(|row| row.as_array().cloned().unwrap_or_default())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn results_should_map_rows() {
+        let root = root(
+            r#"[
+                [1, "1234", 7, "Ann Smith", "NZL", "F", "", "Zeno", "D", "1800", 950, "850"],
+                ["=2", 5678, 8, "Bob Jones", "AUS", "M", "", "Rush", "B", "", "900.5", ""]
+            ]"#,
+        );
+        let (results, errors) = root.results();
+        assert!(errors.is_empty());
+        assert_eq!(root.task_count(), 2);
+        assert_eq!(
+            results[0],
+            HighCloudResult {
+                row: 0,
+                rank: "1".to_string(),
+                pin: "1234".to_string(),
+                name: "Ann Smith".to_string(),
+                nation: "NZL".to_string(),
+                gender: "F".to_string(),
+                glider: "Zeno".to_string(),
+                task_scores: vec![Some(950.0), Some(850.0)],
+                total: Some(1800.0),
+            }
+        );
+        assert_eq!(results[1].rank, "=2");
+        assert_eq!(results[1].pin, "5678");
+        assert_eq!(results[1].task_scores, vec![Some(900.5), None]);
+        assert_eq!(results[1].total, Some(900.5));
+    }
+
+    #[test]
+    fn results_should_report_bad_rows() {
+        let root = root(
+            r#"[
+                [1, "1234", 7, "Ann Smith", "NZL", "F", "", "Zeno", "D", "", 950, 850],
+                [2, "5678"],
+                "not a row",
+                [3, null, 9, "Cat Brown", "NZL", "F", "", "Rush", "B", "", "n/a", 400]
+            ]"#,
+        );
+        let (results, errors) = root.results();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].pin, "");
+        assert_eq!(results[1].task_scores, vec![None, Some(400.0)]);
+        assert_eq!(results[1].total, Some(400.0));
+        assert_eq!(
+            errors.iter().map(|e| e.row).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn tasks_should_be_counted_past_blank_scores() {
+        let root = root(
+            r#"[
+                [1, "1234", 7, "Ann Smith", "NZL", "F", "", "Zeno", "D", "", "", 850, 700],
+                [2, "5678", 8, "Bob Jones", "AUS", "M", "", "Rush", "B", "", null, 800]
+            ]"#,
+        );
+        let (results, errors) = root.results();
+        assert!(errors.is_empty());
+        assert_eq!(root.task_count(), 3);
+        assert_eq!(results[0].task_scores, vec![None, Some(850.0), Some(700.0)]);
+        assert_eq!(results[1].task_scores, vec![None, Some(800.0), None]);
+        assert_eq!(results[1].total, Some(800.0));
+    }
+}
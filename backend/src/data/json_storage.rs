@@ -0,0 +1,191 @@
+use anyhow::Result;
+use frontend::prs_data_types::{Competition, Discipline, Pilot, Ranking, Root};
+use std::{
+    path::PathBuf,
+    sync::{PoisonError, RwLock},
+};
+
+use super::{data_access::save_data, storage::Storage};
+
+/// The whole data set held in memory and written back to a single JSON file.
+/// Without a `data_path` changes are only kept in memory.
+pub struct JsonStorage {
+    data: RwLock<Root>,
+    data_path: Option<PathBuf>,
+}
+
+impl JsonStorage {
+    pub fn new(data: Root, data_path: Option<PathBuf>) -> Self {
+        JsonStorage {
+            data: RwLock::new(data),
+            data_path,
+        }
+    }
+
+    /// Read from the data under the lock, cloning only what `read` returns
+    fn read<T>(&self, read: impl FnOnce(&Root) -> T) -> T {
+        read(&self.data.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Apply a change to a copy of the data, persist it and only then make it
+    /// visible so a failed write leaves the current data untouched
+    fn update(&self, change: impl FnOnce(&mut Root)) -> Result<()> {
+        let mut data = self.data.write().unwrap_or_else(PoisonError::into_inner);
+        let mut updated = data.clone();
+        change(&mut updated);
+        if let Some(path) = &self.data_path {
+            save_data(&updated, path)?;
+        }
+        *data = updated;
+        Ok(())
+    }
+}
+
+fn upsert_competition(data: &mut Root, competition: &Competition) {
+    match data
+        .competitions
+        .iter_mut()
+        .find(|c| c.id == competition.id)
+    {
+        Some(existing) => *existing = competition.clone(),
+        None => data.competitions.push(competition.clone()),
+    }
+}
+
+fn upsert_ranking(data: &mut Root, ranking: &Ranking) {
+    match data
+        .rankings
+        .iter_mut()
+        .find(|r| r.date == ranking.date && r.discipline == ranking.discipline)
+    {
+        Some(existing) => *existing = ranking.clone(),
+        None => data.rankings.push(ranking.clone()),
+    }
+}
+
+impl Storage for JsonStorage {
+    fn pilots(&self) -> Result<Vec<Pilot>> {
+        Ok(self.read(|data| data.pilots.clone()))
+    }
+
+    fn pilot(&self, pin: &str) -> Result<Option<Pilot>> {
+        Ok(self.read(|data| data.pilots.iter().find(|p| p.pin == pin).cloned()))
+    }
+
+    fn competitions(&self) -> Result<Vec<Competition>> {
+        Ok(self.read(|data| data.competitions.clone()))
+    }
+
+    fn competition(&self, id: &str) -> Result<Option<Competition>> {
+        Ok(self.read(|data| data.competitions.iter().find(|c| c.id == id).cloned()))
+    }
+
+    fn pilot_competitions(&self, pin: &str) -> Result<Vec<Competition>> {
+        Ok(self.read(|data| {
+            data.competitions
+                .iter()
+                .filter(|c| c.placings.iter().any(|placing| placing.pilot.pin == pin))
+                .cloned()
+                .collect()
+        }))
+    }
+
+    fn rankings(&self) -> Result<Vec<Ranking>> {
+        Ok(self.read(|data| data.rankings.clone()))
+    }
+
+    fn ranking(&self, date: &str, discipline: Discipline) -> Result<Option<Ranking>> {
+        Ok(self.read(|data| {
+            data.rankings
+                .iter()
+                .find(|r| r.date == date && r.discipline == discipline)
+                .cloned()
+        }))
+    }
+
+    fn admin_users(&self) -> Result<Vec<String>> {
+        Ok(self.read(|data| data.admin_users.clone()))
+    }
+
+    fn save_pilot(&self, pilot: &Pilot) -> Result<()> {
+        self.update(
+            |data| match data.pilots.iter_mut().find(|p| p.pin == pilot.pin) {
+                Some(existing) => *existing = pilot.clone(),
+                None => data.pilots.push(pilot.clone()),
+            },
+        )
+    }
+
+    fn save_competition(&self, competition: &Competition) -> Result<()> {
+        self.update(|data| upsert_competition(data, competition))
+    }
+
+    fn save_ranking(&self, ranking: &Ranking) -> Result<()> {
+        self.update(|data| upsert_ranking(data, ranking))
+    }
+
+    fn save_all(&self, competitions: &[Competition], rankings: &[Ranking]) -> Result<()> {
+        self.update(|data| {
+            competitions
+                .iter()
+                .for_each(|competition| upsert_competition(data, competition));
+            rankings
+                .iter()
+                .for_each(|ranking| upsert_ranking(data, ranking));
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frontend::prs_data_types::CompetitionKind;
+
+    #[test]
+    fn save_competition_should_replace_existing() -> Result<()> {
+        let storage = JsonStorage::new(Root::default(), None);
+        let mut competition = Competition {
+            id: "2022-01-01-Wanaka".to_string(),
+            name: "Wanaka".to_string(),
+            ..Default::default()
+        };
+        storage.save_competition(&competition)?;
+        competition.name = "Wanaka Open".to_string();
+        storage.save_competition(&competition)?;
+
+        assert_eq!(storage.competitions()?.len(), 1);
+        assert_eq!(
+            storage.competition("2022-01-01-Wanaka")?.unwrap().name,
+            "Wanaka Open"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn competitions_saved_before_kinds_should_keep_overseas() -> Result<()> {
+        let stored = |json: &str| -> Result<CompetitionKind> {
+            let mut competition = serde_json::to_value(Competition::default())?;
+            let fields = competition.as_object_mut().unwrap();
+            fields.remove("kind");
+            let older: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+            fields.extend(older);
+            Ok(serde_json::from_value::<Competition>(competition)?.kind)
+        };
+        assert_eq!(
+            stored(r#"{"overseas":true}"#)?,
+            CompetitionKind::OverseasFai
+        );
+        assert_eq!(stored(r#"{"overseas":false}"#)?, CompetitionKind::Local);
+        assert_eq!(
+            stored(r#"{"overseas":true,"kind":"overseasLeague"}"#)?,
+            CompetitionKind::OverseasLeague
+        );
+
+        let league = Competition {
+            kind: CompetitionKind::OverseasLeague,
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_value(&league)?["overseas"], true);
+        Ok(())
+    }
+}
@@ -1,3 +1,9 @@
+pub mod civl_data_types;
 pub mod constants;
 pub mod data_access;
+pub mod fsdb_data_types;
 pub mod highcloud_data_types;
+pub mod json_storage;
+pub mod scoring_config;
+pub mod sqlite_storage;
+pub mod storage;
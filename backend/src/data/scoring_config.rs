@@ -0,0 +1,382 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use frontend::prs_data_types::{Competition, Discipline, RankingCategory, TaskQualityMode};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env, fs, path::Path};
+
+use super::constants::constants;
+
+pub const SCORING_CONFIG_PATH: &str = "./data/scoring.json";
+
+/// The scoring rules in force from a given date
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoringRules {
+    pub version: String,
+    /// Competitions and rankings on or after this date (YYYY-MM-DD) use these
+    /// rules
+    pub effective_from: String,
+    pub td_a: f64,
+    pub td_b: f64,
+    /// Days over which a result decays away
+    pub td_period: f64,
+    pub pn_max: f64,
+    pub pq_min: f64,
+    /// Number of results counted towards a pilot's ranking
+    pub best_results: usize,
+    /// Months of competitions counted in a ranking
+    pub ranking_months: u32,
+    /// Months of competitions averaged for Pn
+    pub pn_months: u32,
+    /// Months before a competition the ranking used for its Pq may be from
+    #[serde(default = "lookback_months")]
+    pub pq_ranking_months: u32,
+    /// Months of NZ competitions the suggested exchange rate is worked out
+    /// from
+    #[serde(default = "lookback_months")]
+    pub exchange_rate_months: u32,
+    /// Most overseas results counted in a ranking
+    pub max_overseas_results: usize,
+    /// Ta indexed by number of tasks, the last value covers any higher count
+    pub task_quality: Vec<f64>,
+    /// Ta tables for disciplines that don't use `task_quality`
+    #[serde(default = "discipline_task_quality")]
+    pub discipline_task_quality: HashMap<Discipline, Vec<f64>>,
+    #[serde(default)]
+    pub ta_mode: TaskQualityMode,
+    #[serde(default)]
+    pub pp_mode: PilotPointsMode,
+    /// Share of Pp from the score in the blended mode
+    #[serde(default = "pp_score_weight")]
+    pub pp_score_weight: f64,
+}
+
+/// What a placing's Pp is worked out from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PilotPointsMode {
+    /// Pplacing, from the pilot's place in the field
+    #[default]
+    Placing,
+    /// The pilot's score as a share of the winner's
+    Score,
+    /// Pplacing and the score share weighted by `pp_score_weight`
+    Blended,
+}
+
+fn lookback_months() -> u32 {
+    24
+}
+
+fn pp_score_weight() -> f64 {
+    0.5
+}
+
+/// Accuracy competitions fly many short rounds so need more of them for full
+/// task quality
+fn discipline_task_quality() -> HashMap<Discipline, Vec<f64>> {
+    HashMap::from([(
+        Discipline::Accuracy,
+        vec![0.0, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0],
+    )])
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            version: "original".to_string(),
+            effective_from: "1900-01-01".to_string(),
+            td_a: constants::TD_A,
+            td_b: constants::TD_B,
+            td_period: constants::TD_PERIOD,
+            pn_max: constants::PN_MAX,
+            pq_min: constants::PQ_MIN,
+            best_results: 4,
+            ranking_months: 36,
+            pn_months: 24,
+            pq_ranking_months: lookback_months(),
+            exchange_rate_months: lookback_months(),
+            max_overseas_results: 2,
+            task_quality: vec![0.0, 0.4, 0.6, 0.8, 0.9, 1.0],
+            discipline_task_quality: discipline_task_quality(),
+            ta_mode: TaskQualityMode::TaskCount,
+            pp_mode: PilotPointsMode::Placing,
+            pp_score_weight: pp_score_weight(),
+        }
+    }
+}
+
+impl ScoringRules {
+    /// Ta for a competition in the discipline with the given number of tasks
+    pub fn task_quality(&self, discipline: Discipline, number_of_tasks: usize) -> f64 {
+        let task_quality = self.task_quality_table(discipline);
+        task_quality
+            .get(number_of_tasks)
+            .or(task_quality.last())
+            .cloned()
+            .unwrap_or(1.0)
+    }
+
+    /// Ta for a summed task validity, in proportion between the Ta for the
+    /// whole numbers of tasks either side of it
+    pub fn validity_task_quality(&self, discipline: Discipline, total_validity: f64) -> f64 {
+        let total_validity = total_validity.max(0.0);
+        let tasks = total_validity.floor() as usize;
+        let below = self.task_quality(discipline, tasks);
+        let above = self.task_quality(discipline, tasks + 1);
+        below + (above - below) * total_validity.fract()
+    }
+
+    /// Ta for the competition in the rules' Ta mode
+    pub fn competition_task_quality(&self, competition: &Competition) -> f64 {
+        match (self.ta_mode, competition.summed_validity()) {
+            (TaskQualityMode::TaskValidity, Some(total_validity)) => {
+                self.validity_task_quality(competition.discipline, total_validity)
+            }
+            _ => self.task_quality(competition.discipline, competition.num_tasks as usize),
+        }
+    }
+
+    /// The placing factor Pp is raised from in the rules' Pp mode. Placings
+    /// without a score, or a field without a winning score, use Pplacing.
+    pub fn placing_factor(&self, pplacing: f64, score: Option<f64>, winner_score: f64) -> f64 {
+        let score_share = match score {
+            Some(score) if winner_score > 0.0 => (score / winner_score).clamp(0.0, 1.0),
+            _ => return pplacing,
+        };
+        match self.pp_mode {
+            PilotPointsMode::Placing => pplacing,
+            PilotPointsMode::Score => score_share,
+            PilotPointsMode::Blended => {
+                self.pp_score_weight * score_share + (1.0 - self.pp_score_weight) * pplacing
+            }
+        }
+    }
+
+    fn task_quality_table(&self, discipline: Discipline) -> &Vec<f64> {
+        self.discipline_task_quality
+            .get(&discipline)
+            .unwrap_or(&self.task_quality)
+    }
+}
+
+/// Every version of the scoring rules, historic and current, and the
+/// categories rankings are published for
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoringConfig {
+    pub versions: Vec<ScoringRules>,
+    #[serde(default = "categories")]
+    pub categories: Vec<RankingCategory>,
+    /// Rules to try out against the published ones. They are only used by a
+    /// trial recalculation and never by published rankings.
+    #[serde(default)]
+    pub trial: Option<ScoringRules>,
+}
+
+fn categories() -> Vec<RankingCategory> {
+    vec![
+        RankingCategory {
+            id: "women".to_string(),
+            name: "Women".to_string(),
+            gender: Some("FEMALE".to_string()),
+            ..Default::default()
+        },
+        RankingCategory {
+            id: "juniors".to_string(),
+            name: "Juniors".to_string(),
+            max_age: Some(25),
+            ..Default::default()
+        },
+        RankingCategory {
+            id: "masters".to_string(),
+            name: "Masters".to_string(),
+            min_age: Some(50),
+            ..Default::default()
+        },
+    ]
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            versions: vec![ScoringRules::default()],
+            categories: categories(),
+            trial: None,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// Read the config file at `NZPRS_SCORING_CONFIG` (defaults to
+    /// `./data/scoring.json`). Without a file the original rules apply.
+    pub fn open() -> Result<ScoringConfig> {
+        let path = env::var("NZPRS_SCORING_CONFIG").unwrap_or(SCORING_CONFIG_PATH.to_string());
+        let path = Path::new(&path);
+        if !path.exists() {
+            tracing::info!("No scoring config at {:?}, using the original rules", path);
+            return Ok(ScoringConfig::default());
+        }
+        ScoringConfig::load(path)
+    }
+
+    pub fn load(path: &Path) -> Result<ScoringConfig> {
+        let mut config: ScoringConfig = serde_json::from_str(&fs::read_to_string(path)?)?;
+        config
+            .versions
+            .sort_by(|a, b| a.effective_from.cmp(&b.effective_from));
+        if config.versions.is_empty() {
+            return Err(anyhow!("No scoring versions in {:?}", path));
+        }
+        for rules in config.versions.iter().chain(&config.trial) {
+            if rules.effective_from.parse::<NaiveDate>().is_err() {
+                return Err(anyhow!("Version {} has an invalid date", rules.version));
+            }
+            if rules.task_quality.is_empty()
+                || rules.discipline_task_quality.values().any(|t| t.is_empty())
+            {
+                return Err(anyhow!(
+                    "Version {} has no task quality table",
+                    rules.version
+                ));
+            }
+        }
+        for (i, category) in config.categories.iter().enumerate() {
+            if config.categories[..i].iter().any(|c| c.id == category.id) {
+                return Err(anyhow!("Category {} is defined twice", category.id));
+            }
+        }
+        Ok(config)
+    }
+
+    /// The config with the trial rules in force from their effective date, in
+    /// place of any version of the same name
+    pub fn with_trial(&self) -> Option<ScoringConfig> {
+        let trial = self.trial.as_ref()?;
+        let mut versions: Vec<ScoringRules> = self
+            .versions
+            .iter()
+            .filter(|r| r.version != trial.version)
+            .chain([trial])
+            .cloned()
+            .collect();
+        versions.sort_by(|a, b| a.effective_from.cmp(&b.effective_from));
+        Some(ScoringConfig {
+            versions,
+            categories: self.categories.clone(),
+            trial: None,
+        })
+    }
+
+    pub fn category(&self, id: &str) -> Option<&RankingCategory> {
+        self.categories.iter().find(|c| c.id == id)
+    }
+
+    /// The rules in force on the date. Dates before the first version use the
+    /// first version.
+    pub fn rules_for(&self, date: &NaiveDate) -> &ScoringRules {
+        self.versions
+            .iter()
+            .rev()
+            .find(|r| match r.effective_from.parse::<NaiveDate>() {
+                Ok(effective_from) => effective_from.le(date),
+                Err(_) => false,
+            })
+            .or(self.versions.first())
+            .expect("Scoring config has no versions")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_should_follow_effective_date() -> Result<()> {
+        let dir = env::temp_dir().join("nzprs_scoring_config_test");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("scoring.json");
+        let revised = ScoringRules {
+            version: "2024".to_string(),
+            effective_from: "2024-01-01".to_string(),
+            best_results: 5,
+            ..Default::default()
+        };
+        fs::write(
+            &path,
+            serde_json::to_string(&ScoringConfig {
+                versions: vec![revised.clone(), ScoringRules::default()],
+                ..Default::default()
+            })?,
+        )?;
+        let config = ScoringConfig::load(&path)?;
+
+        let before = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        let after = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(config.rules_for(&before).version, "original");
+        assert_eq!(config.rules_for(&after), &revised);
+        assert_eq!(
+            config
+                .rules_for(&after)
+                .task_quality(Discipline::ParaglidingXc, 9),
+            1.0
+        );
+        assert_eq!(
+            config
+                .rules_for(&after)
+                .task_quality(Discipline::Accuracy, 5),
+            0.6
+        );
+        assert_eq!(config.category("juniors").unwrap().max_age, Some(25));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn trial_rules_should_only_be_used_by_trial_config() {
+        let trial = ScoringRules {
+            version: "score-trial".to_string(),
+            effective_from: "2020-01-01".to_string(),
+            pp_mode: PilotPointsMode::Blended,
+            ..Default::default()
+        };
+        let config = ScoringConfig {
+            trial: Some(trial.clone()),
+            ..Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        assert_eq!(config.rules_for(&date).pp_mode, PilotPointsMode::Placing);
+
+        let trial_config = config.with_trial().unwrap();
+        assert_eq!(trial_config.rules_for(&date), &trial);
+        assert_eq!(trial_config.versions.len(), 2);
+        assert!(ScoringConfig::default().with_trial().is_none());
+    }
+
+    #[test]
+    fn validity_mode_should_scale_ta_by_validity() {
+        let rules = ScoringRules {
+            ta_mode: TaskQualityMode::TaskValidity,
+            ..Default::default()
+        };
+        let competition = Competition {
+            num_tasks: 3,
+            task_validity: vec![1.0, 1.0, 0.5],
+            ..Default::default()
+        };
+        // Halfway between two tasks at 0.6 and three at 0.8
+        assert!((rules.competition_task_quality(&competition) - 0.7).abs() < 1e-9);
+        assert_eq!(
+            ScoringRules::default().competition_task_quality(&competition),
+            0.8
+        );
+        let without_validity = Competition {
+            num_tasks: 3,
+            ..Default::default()
+        };
+        assert_eq!(rules.competition_task_quality(&without_validity), 0.8);
+        assert_eq!(
+            rules.validity_task_quality(Discipline::ParaglidingXc, 7.5),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use anyhow::Result;
+use frontend::prs_data_types::{Competition, Discipline, Pilot, Ranking, Root};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use super::storage::Storage;
+
+/// Records are kept as JSON documents so the API shapes don't depend on the
+/// schema, with the columns we query on pulled out and indexed alongside them.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS pilots (
+        pin TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS competitions (
+        id TEXT PRIMARY KEY,
+        comp_date TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS competitions_comp_date ON competitions (comp_date);
+    CREATE TABLE IF NOT EXISTS competition_pilots (
+        competition_id TEXT NOT NULL REFERENCES competitions (id) ON DELETE CASCADE,
+        pin TEXT NOT NULL,
+        PRIMARY KEY (competition_id, pin)
+    );
+    CREATE INDEX IF NOT EXISTS competition_pilots_pin ON competition_pilots (pin);
+    CREATE TABLE IF NOT EXISTS rankings (
+        date TEXT NOT NULL,
+        discipline TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (date, discipline)
+    );
+    CREATE TABLE IF NOT EXISTS admin_users (
+        email TEXT PRIMARY KEY
+    );
+";
+
+/// Embedded SQLite database
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        SqliteStorage::from_connection(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        SqliteStorage::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<Self> {
+        connection.pragma_update(None, "foreign_keys", "ON")?;
+        connection.execute_batch(SCHEMA)?;
+        add_ranking_disciplines(&connection)?;
+        Ok(SqliteStorage {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        let count: i64 = self.connection().query_row(
+            "SELECT (SELECT COUNT(*) FROM pilots) + (SELECT COUNT(*) FROM competitions)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count == 0)
+    }
+
+    /// Load a full data set in a single transaction
+    pub fn import(&self, data: &Root) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        for pilot in &data.pilots {
+            transaction.execute(
+                "INSERT OR REPLACE INTO pilots (pin, data) VALUES (?1, ?2)",
+                params![pilot.pin, serde_json::to_string(pilot)?],
+            )?;
+        }
+        for email in &data.admin_users {
+            transaction.execute(
+                "INSERT OR IGNORE INTO admin_users (email) VALUES (?1)",
+                params![email],
+            )?;
+        }
+        for competition in &data.competitions {
+            upsert_competition(&transaction, competition)?;
+        }
+        for ranking in &data.rankings {
+            upsert_ranking(&transaction, ranking)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn query_all<T: DeserializeOwned>(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<T>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(sql)?;
+        let rows = statement.query_map(params, |row| row.get::<_, String>(0))?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(serde_json::from_str(&row?)?);
+        }
+        Ok(results)
+    }
+
+    fn query_one<T: DeserializeOwned>(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Option<T>> {
+        let data: Option<String> = self
+            .connection()
+            .query_row(sql, params, |row| row.get(0))
+            .optional()?;
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Databases created before disciplines have rankings keyed by date alone.
+/// Those rankings are all paragliding XC.
+fn add_ranking_disciplines(connection: &Connection) -> Result<()> {
+    let has_discipline: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('rankings') WHERE name = 'discipline'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_discipline {
+        tracing::info!("Adding disciplines to SQLite rankings");
+        connection.execute_batch(&format!(
+            "BEGIN;
+             ALTER TABLE rankings RENAME TO rankings_by_date;
+             {SCHEMA}
+             INSERT INTO rankings (date, discipline, data)
+                 SELECT date, '{}', data FROM rankings_by_date;
+             DROP TABLE rankings_by_date;
+             COMMIT;",
+            Discipline::ParaglidingXc.id()
+        ))?;
+    }
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn upsert_competition(transaction: &Transaction, competition: &Competition) -> Result<()> {
+    transaction.execute(
+        "INSERT OR REPLACE INTO competitions (id, comp_date, data) VALUES (?1, ?2, ?3)",
+        params![competition.id, competition.comp_date, to_json(competition)?],
+    )?;
+    transaction.execute(
+        "DELETE FROM competition_pilots WHERE competition_id = ?1",
+        params![competition.id],
+    )?;
+    for placing in competition
+        .placings
+        .iter()
+        .filter(|p| !p.pilot.pin.is_empty())
+    {
+        transaction.execute(
+            "INSERT OR IGNORE INTO competition_pilots (competition_id, pin) VALUES (?1, ?2)",
+            params![competition.id, placing.pilot.pin],
+        )?;
+    }
+    Ok(())
+}
+
+fn upsert_ranking(transaction: &Transaction, ranking: &Ranking) -> Result<()> {
+    transaction.execute(
+        "INSERT OR REPLACE INTO rankings (date, discipline, data) VALUES (?1, ?2, ?3)",
+        params![ranking.date, ranking.discipline.id(), to_json(ranking)?],
+    )?;
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn pilots(&self) -> Result<Vec<Pilot>> {
+        self.query_all("SELECT data FROM pilots ORDER BY pin", [])
+    }
+
+    fn pilot(&self, pin: &str) -> Result<Option<Pilot>> {
+        self.query_one("SELECT data FROM pilots WHERE pin = ?1", params![pin])
+    }
+
+    fn competitions(&self) -> Result<Vec<Competition>> {
+        self.query_all("SELECT data FROM competitions ORDER BY comp_date", [])
+    }
+
+    fn competition(&self, id: &str) -> Result<Option<Competition>> {
+        self.query_one("SELECT data FROM competitions WHERE id = ?1", params![id])
+    }
+
+    fn pilot_competitions(&self, pin: &str) -> Result<Vec<Competition>> {
+        self.query_all(
+            "SELECT c.data FROM competitions c
+             JOIN competition_pilots cp ON cp.competition_id = c.id
+             WHERE cp.pin = ?1
+             ORDER BY c.comp_date",
+            params![pin],
+        )
+    }
+
+    fn rankings(&self) -> Result<Vec<Ranking>> {
+        self.query_all("SELECT data FROM rankings ORDER BY date, discipline", [])
+    }
+
+    fn ranking(&self, date: &str, discipline: Discipline) -> Result<Option<Ranking>> {
+        self.query_one(
+            "SELECT data FROM rankings WHERE date = ?1 AND discipline = ?2",
+            params![date, discipline.id()],
+        )
+    }
+
+    fn admin_users(&self) -> Result<Vec<String>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT email FROM admin_users")?;
+        let emails = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(emails)
+    }
+
+    fn save_pilot(&self, pilot: &Pilot) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO pilots (pin, data) VALUES (?1, ?2)",
+            params![pilot.pin, to_json(pilot)?],
+        )?;
+        Ok(())
+    }
+
+    fn save_competition(&self, competition: &Competition) -> Result<()> {
+        self.save_all(&[competition.clone()], &[])
+    }
+
+    fn save_ranking(&self, ranking: &Ranking) -> Result<()> {
+        self.save_all(&[], &[ranking.clone()])
+    }
+
+    fn save_all(&self, competitions: &[Competition], rankings: &[Ranking]) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        for competition in competitions {
+            upsert_competition(&transaction, competition)?;
+        }
+        for ranking in rankings {
+            upsert_ranking(&transaction, ranking)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frontend::prs_data_types::{CompetitionPilot, Placing};
+
+    fn test_data() -> Root {
+        Root {
+            pilots: vec![Pilot {
+                pin: "1001".to_string(),
+                first_name: "First".to_string(),
+                last_name: "Pilot".to_string(),
+                gender: "MALE".to_string(),
+                ..Default::default()
+            }],
+            competitions: vec![Competition {
+                id: "2022-01-01-Wanaka".to_string(),
+                comp_date: "2022-01-01".to_string(),
+                placings: vec![Placing {
+                    pilot: CompetitionPilot {
+                        pin: "1001".to_string(),
+                        ..Default::default()
+                    },
+                    place: 1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            rankings: vec![Ranking {
+                id: "2022-02-01".to_string(),
+                date: "2022-02-01".to_string(),
+                ranking_points: vec![],
+                ..Default::default()
+            }],
+            admin_users: vec!["admin@nzhgpa.org.nz".to_string()],
+        }
+    }
+
+    #[test]
+    fn imported_data_should_be_queryable() -> Result<()> {
+        let storage = SqliteStorage::open_in_memory()?;
+        assert_eq!(storage.is_empty()?, true);
+        storage.import(&test_data())?;
+
+        assert_eq!(storage.is_empty()?, false);
+        assert_eq!(storage.pilot("1001")?, Some(test_data().pilots[0].clone()));
+        assert_eq!(storage.competition("2022-01-01-Wanaka")?.is_some(), true);
+        assert_eq!(storage.pilot_competitions("1001")?.len(), 1);
+        assert_eq!(storage.pilot_competitions("1002")?.len(), 0);
+        assert_eq!(
+            storage
+                .ranking("2022-02-01", Discipline::ParaglidingXc)?
+                .is_some(),
+            true
+        );
+        assert_eq!(
+            storage
+                .ranking("2022-02-01", Discipline::Accuracy)?
+                .is_none(),
+            true
+        );
+        assert_eq!(storage.admin_users()?, test_data().admin_users);
+        Ok(())
+    }
+
+    #[test]
+    fn saved_competition_should_replace_pilot_index() -> Result<()> {
+        let storage = SqliteStorage::open_in_memory()?;
+        storage.import(&test_data())?;
+        let mut competition = test_data().competitions[0].clone();
+        competition.placings[0].pilot.pin = "1002".to_string();
+        storage.save_competition(&competition)?;
+
+        assert_eq!(storage.competitions()?.len(), 1);
+        assert_eq!(storage.pilot_competitions("1001")?.len(), 0);
+        assert_eq!(storage.pilot_competitions("1002")?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn rankings_keyed_by_date_should_become_paragliding() -> Result<()> {
+        let connection = Connection::open_in_memory()?;
+        connection
+            .execute_batch("CREATE TABLE rankings (date TEXT PRIMARY KEY, data TEXT NOT NULL);")?;
+        connection.execute(
+            "INSERT INTO rankings (date, data) VALUES (?1, ?2)",
+            params!["2022-02-01", to_json(&test_data().rankings[0])?],
+        )?;
+        let storage = SqliteStorage::from_connection(connection)?;
+        let mut ranking = test_data().rankings[0].clone();
+        ranking.discipline = Discipline::HangGlidingXc;
+        storage.save_ranking(&ranking)?;
+
+        assert_eq!(storage.rankings()?.len(), 2);
+        assert_eq!(
+            storage
+                .ranking("2022-02-01", Discipline::ParaglidingXc)?
+                .is_some(),
+            true
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use frontend::prs_data_types::{Competition, Discipline, Pilot, Ranking, Root};
+use std::{env, path::PathBuf, sync::Arc};
+use tokio::sync::Mutex;
+
+use super::{
+    data_access::{load_data, DATA_PATH},
+    json_storage::JsonStorage,
+    scoring_config::ScoringConfig,
+    sqlite_storage::SqliteStorage,
+};
+
+pub const SQLITE_PATH: &str = "./data/nzprs.sqlite";
+
+/// Access to the pilots, competitions, rankings and admin users backing the API
+pub trait Storage: Send + Sync {
+    fn pilots(&self) -> Result<Vec<Pilot>>;
+    fn pilot(&self, pin: &str) -> Result<Option<Pilot>>;
+    fn competitions(&self) -> Result<Vec<Competition>>;
+    fn competition(&self, id: &str) -> Result<Option<Competition>>;
+    /// Competitions with a placing for the pilot
+    fn pilot_competitions(&self, pin: &str) -> Result<Vec<Competition>>;
+    fn rankings(&self) -> Result<Vec<Ranking>>;
+    fn ranking(&self, date: &str, discipline: Discipline) -> Result<Option<Ranking>>;
+    fn admin_users(&self) -> Result<Vec<String>>;
+    /// Insert or replace a pilot by pin
+    fn save_pilot(&self, pilot: &Pilot) -> Result<()>;
+    /// Insert or replace a competition by id
+    fn save_competition(&self, competition: &Competition) -> Result<()>;
+    /// Insert or replace a ranking by date and discipline
+    fn save_ranking(&self, ranking: &Ranking) -> Result<()>;
+    /// Insert or replace competitions and rankings together, either all are
+    /// written or none are
+    fn save_all(&self, competitions: &[Competition], rankings: &[Ranking]) -> Result<()>;
+}
+
+/// State shared by every route
+#[derive(Clone)]
+pub struct AppState {
+    pub storage: Arc<dyn Storage>,
+    pub scoring: Arc<ScoringConfig>,
+    /// Held from reading to saving by anything that recalculates and saves, so
+    /// a change isn't overwritten by one calculated from older data
+    pub writes: Arc<Mutex<()>>,
+}
+
+impl AppState {
+    /// Scores with the original rules until `with_scoring` is used
+    pub fn new(storage: impl Storage + 'static) -> Self {
+        AppState {
+            storage: Arc::new(storage),
+            scoring: Arc::new(ScoringConfig::default()),
+            writes: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn with_scoring(self, scoring: ScoringConfig) -> Self {
+        AppState {
+            scoring: Arc::new(scoring),
+            ..self
+        }
+    }
+}
+
+/// Keep everything in memory, nothing is written to disk
+impl From<Root> for AppState {
+    fn from(data: Root) -> Self {
+        AppState::new(JsonStorage::new(data, None))
+    }
+}
+
+/// Open the storage selected by `NZPRS_STORAGE` (`json` or `sqlite`, defaults
+/// to `json`). An empty SQLite database is seeded from the JSON data file.
+/// Scoring uses the config from `ScoringConfig::open`.
+pub fn open_storage() -> Result<AppState> {
+    let state = match env::var("NZPRS_STORAGE").unwrap_or_default().as_str() {
+        "sqlite" => {
+            let path = env::var("NZPRS_SQLITE_PATH").unwrap_or(SQLITE_PATH.to_string());
+            let storage = SqliteStorage::open(&PathBuf::from(path))?;
+            if storage.is_empty()? {
+                tracing::info!("Seeding SQLite storage from {}", DATA_PATH);
+                storage.import(&load_data()?)?;
+            }
+            AppState::new(storage)
+        }
+        _ => AppState::new(JsonStorage::new(
+            load_data()?,
+            Some(PathBuf::from(DATA_PATH)),
+        )),
+    };
+    Ok(state.with_scoring(ScoringConfig::open()?))
+}
+
+/// Log a storage failure and turn it into a server error
+pub fn storage_error(error: anyhow::Error) -> Response {
+    tracing::error!("Storage error: {}", error);
+    (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use scraper::Html;
+
+use super::{Importer, SourceResult, SourceResults};
+use crate::data::civl_data_types::CivlCompetition;
+use frontend::prs_data_types::{
+    Competition, CompetitionKind, ImportRowError, PlacingStatus, WprsDetails,
+};
+
+/// A WPRS competition's page on civlcomps.org. Only the NZ pilots are kept
+pub struct CivlImporter;
+
+impl Importer for CivlImporter {
+    fn url(&self, id: &str) -> Option<String> {
+        Some(format!(
+            "https://civlcomps.org/ranking/paragliding-xc/competition?id={}",
+            id
+        ))
+    }
+
+    fn parse(&self, id: &str, body: &str) -> Result<SourceResults> {
+        let (civl_competition, errors) = CivlCompetition::parse(&Html::parse_document(body))?;
+        Ok(map_civl(civl_competition, id, errors))
+    }
+}
+
+/// Map CIVL's results to a draft overseas competition of the NZ pilots
+fn map_civl(
+    civl_competition: CivlCompetition,
+    id: &str,
+    errors: Vec<ImportRowError>,
+) -> SourceResults {
+    let format_date = |date: NaiveDate| date.format("%Y-%m-%d").to_string();
+    // Ranks are kept for every pilot so ties read the same as on the page
+    // before the other nations are left out
+    let mut previous_rank = String::new();
+    let results = civl_competition
+        .pilots
+        .iter()
+        .enumerate()
+        .map(|(row, pilot)| {
+            if pilot.rank.trim().is_empty() {
+                return (row, pilot, previous_rank.clone());
+            }
+            if PlacingStatus::from_code(&pilot.rank).is_none() {
+                previous_rank = pilot.rank.clone();
+            }
+            (row, pilot, pilot.rank.clone())
+        })
+        .filter(|(_, pilot, _)| pilot.nation.contains("NZL"))
+        .map(|(row, pilot, rank)| SourceResult {
+            row,
+            source_id: pilot.civl_id.clone(),
+            name: pilot.name.clone(),
+            rank,
+            fai_points: pilot.wprs_points,
+            ..Default::default()
+        })
+        .collect();
+    SourceResults {
+        competition: Competition {
+            comp_date: format_date(civl_competition.start_date),
+            name: civl_competition.name,
+            location: civl_competition.country.clone(),
+            kind: CompetitionKind::OverseasFai,
+            civl_id: Some(id.to_string()),
+            ..Default::default()
+        },
+        results,
+        errors,
+        wprs: Some(WprsDetails {
+            end_date: civl_competition.end_date.map(format_date),
+            category: civl_competition.category,
+            country: civl_competition.country,
+            num_pilots: civl_competition.num_pilots,
+            pq: civl_competition.pq,
+            pn: civl_competition.pn,
+            ta: civl_competition.ta,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::civl_data_types::CivlPilot;
+
+    #[test]
+    fn map_civl_should_keep_nz_pilots() {
+        let civl_pilot = |rank: &str, name: &str, nation: &str| CivlPilot {
+            civl_id: name.to_lowercase(),
+            rank: rank.to_string(),
+            name: name.to_string(),
+            nation: nation.to_string(),
+            wprs_points: 50.0,
+        };
+        let civl_competition = CivlCompetition {
+            name: "Bright Open".to_string(),
+            start_date: NaiveDate::from_ymd_opt(2023, 1, 5).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2023, 1, 12),
+            country: "Australia".to_string(),
+            num_pilots: 4,
+            pilots: vec![
+                civl_pilot("1", "Ann Smith", "AUS"),
+                civl_pilot("2", "Bob Jones", "AUS"),
+                civl_pilot("", "Dan Green", "NZL"),
+                civl_pilot("DNF", "Cat Brown", "NZL"),
+            ],
+            ..Default::default()
+        };
+
+        let results = map_civl(civl_competition, "5859", vec![]);
+
+        assert_eq!(results.competition.comp_date, "2023-01-05");
+        assert_eq!(results.competition.civl_id, Some("5859".to_string()));
+        assert_eq!(results.competition.kind, CompetitionKind::OverseasFai);
+        assert_eq!(results.results.len(), 2);
+        assert_eq!(results.results[0].rank, "2");
+        assert_eq!(results.results[0].source_id, "dan green");
+        assert_eq!(results.results[0].fai_points, 50.0);
+        assert_eq!(results.results[1].rank, "DNF");
+        let wprs = results.wprs.unwrap();
+        assert_eq!(wprs.end_date, Some("2023-01-12".to_string()));
+        assert_eq!(wprs.num_pilots, 4);
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+
+use super::{Importer, SourceResult, SourceResults};
+use frontend::{
+    csv::read_csv,
+    prs_data_types::{Competition, CsvField, CsvMapping, CsvUpload, ImportRowError, PlacingStatus},
+};
+
+/// A results spreadsheet saved as CSV, uploaded with which column holds each
+/// field. Only the placings are read, the rest of the competition is entered
+/// by hand
+pub struct CsvImporter;
+
+impl Importer for CsvImporter {
+    fn url(&self, _id: &str) -> Option<String> {
+        None
+    }
+
+    fn parse(&self, _id: &str, body: &str) -> Result<SourceResults> {
+        let upload: CsvUpload = serde_json::from_str(body).context("Not a CSV upload")?;
+        let mapping = upload.mapping;
+        if mapping.place.is_none() {
+            return Err(anyhow!("Choose the column with the places"));
+        }
+        if [mapping.pin, mapping.first_name, mapping.last_name]
+            .iter()
+            .all(Option::is_none)
+        {
+            return Err(anyhow!("Choose the columns with the pilots' pins or names"));
+        }
+        let rows = read_csv(&upload.contents);
+        let skip = usize::from(mapping.has_header);
+        let mut errors = vec![];
+        // Rows are reported by their line in the file so blank lines don't
+        // throw the numbering out
+        let results: Vec<SourceResult> = rows
+            .iter()
+            .skip(skip)
+            .filter_map(|(line, cells)| read_row(&mapping, *line, cells, &mut errors))
+            .collect();
+        if results.is_empty() {
+            return Err(anyhow!("No results in the file"));
+        }
+        check_places(&results, &mut errors);
+        Ok(SourceResults {
+            competition: Competition::default(),
+            results,
+            errors,
+            wprs: None,
+        })
+    }
+}
+
+/// Read a pilot's result from a row, leaving out rows without a place or a
+/// pilot
+fn read_row(
+    mapping: &CsvMapping,
+    row: usize,
+    cells: &[String],
+    errors: &mut Vec<ImportRowError>,
+) -> Option<SourceResult> {
+    let cell = |field: CsvField| {
+        mapping
+            .column(field)
+            .and_then(|column| cells.get(column))
+            .map(String::as_str)
+            .unwrap_or_default()
+    };
+    let mut error = |message: String| errors.push(ImportRowError { row, message });
+    let rank = cell(CsvField::Place);
+    if rank.is_empty() {
+        error("No place".to_string());
+        return None;
+    }
+    if PlacingStatus::from_code(rank).is_none()
+        && rank.trim_matches('=').trim().parse::<i64>().is_err()
+    {
+        error(format!("Place {} is not a number", rank));
+        return None;
+    }
+    let pin = cell(CsvField::Pin);
+    let name = [cell(CsvField::FirstName), cell(CsvField::LastName)]
+        .iter()
+        .filter(|name| !name.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if pin.is_empty() && name.is_empty() {
+        error("No pilot".to_string());
+        return None;
+    }
+    let score = match cell(CsvField::Score) {
+        "" => None,
+        score => score.parse::<f64>().map_or_else(
+            |_| {
+                error(format!("Score {} is not a number", score));
+                None
+            },
+            Some,
+        ),
+    };
+    let gender = match cell(CsvField::Gender).to_uppercase().as_str() {
+        "" => "",
+        "F" | "FEMALE" | "W" | "WOMAN" => "F",
+        "M" | "MALE" | "MAN" => "M",
+        gender => {
+            error(format!("Gender {} is not F or M", gender));
+            ""
+        }
+    };
+    Some(SourceResult {
+        row,
+        source_id: if pin.is_empty() { name.as_str() } else { pin }.to_string(),
+        pin: pin.to_string(),
+        name,
+        gender: gender.to_string(),
+        rank: rank.to_string(),
+        score,
+        ..Default::default()
+    })
+}
+
+/// Report places taken twice without being marked as a tie with "=", and places
+/// no pilot has. Pilots tied on a place take up the places after it
+fn check_places(results: &[SourceResult], errors: &mut Vec<ImportRowError>) {
+    let mut placed: HashMap<i64, Vec<&SourceResult>> = HashMap::new();
+    for result in results {
+        if let Ok(place) = result.rank.trim_matches('=').trim().parse::<i64>() {
+            placed.entry(place).or_default().push(result);
+        }
+    }
+    let mut places: Vec<i64> = placed.keys().copied().collect();
+    places.sort();
+    let mut next = 1;
+    for place in places {
+        let pilots = &placed[&place];
+        if place > next {
+            errors.push(ImportRowError {
+                row: pilots[0].row,
+                message: if place - next == 1 {
+                    format!("No pilot placed {}", next)
+                } else {
+                    format!("No pilots placed {} to {}", next, place - 1)
+                },
+            });
+        }
+        if pilots.len() > 1 {
+            for pilot in pilots.iter().filter(|pilot| !pilot.rank.contains('=')) {
+                errors.push(ImportRowError {
+                    row: pilot.row,
+                    message: format!("Place {} is taken more than once, mark ties with =", place),
+                });
+            }
+        }
+        next = place + pilots.len() as i64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upload(contents: &str) -> String {
+        let (_, first_row) = &read_csv(contents)[0];
+        serde_json::to_string(&CsvUpload {
+            mapping: CsvMapping::guess(first_row),
+            contents: contents.to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn guess_should_find_columns_by_header() {
+        let header: Vec<String> = ["Pos", "Surname", "First Name", "PIN", "Sex", "Total"]
+            .iter()
+            .map(|cell| cell.to_string())
+            .collect();
+        let mapping = CsvMapping::guess(&header);
+        assert!(mapping.has_header);
+        assert_eq!(mapping.place, Some(0));
+        assert_eq!(mapping.last_name, Some(1));
+        assert_eq!(mapping.first_name, Some(2));
+        assert_eq!(mapping.pin, Some(3));
+        assert_eq!(mapping.gender, Some(4));
+        assert_eq!(mapping.score, Some(5));
+        assert!(!CsvMapping::guess(&["1".to_string()]).has_header);
+    }
+
+    #[test]
+    fn parse_should_read_rows() {
+        let results = CsvImporter
+            .parse(
+                "",
+                &upload(
+                    "Place,Pin,First name,Last name,Gender,Score
+1,1234,Ann,Smith,female,950.5
+=2,,Bob,Jones,m,
+=2,77,Cat,Brown,F,800
+DNF,88,Dan,Gray,M,0",
+                ),
+            )
+            .unwrap();
+        assert!(results.errors.is_empty());
+        assert_eq!(results.results.len(), 4);
+        assert_eq!(results.results[0].row, 1);
+        assert_eq!(results.results[0].pin, "1234");
+        assert_eq!(results.results[0].name, "Ann Smith");
+        assert_eq!(results.results[0].gender, "F");
+        assert_eq!(results.results[0].score, Some(950.5));
+        assert_eq!(results.results[1].source_id, "Bob Jones");
+        assert_eq!(results.results[1].score, None);
+        assert_eq!(results.results[3].rank, "DNF");
+    }
+
+    #[test]
+    fn parse_should_report_invalid_rows() {
+        let results = CsvImporter
+            .parse(
+                "",
+                &upload(
+                    "Place,Pin,First name,Last name,Gender,Score
+1,1,Ann,Smith,F,x
+1,2,Bob,Jones,M,900
+,3,Cat,Brown,F,800
+4,,,,M,10
+5,5,Dan,Gray,?,700",
+                ),
+            )
+            .unwrap();
+        assert_eq!(results.results.len(), 3);
+        assert_eq!(
+            results.errors,
+            vec![
+                ImportRowError {
+                    row: 1,
+                    message: "Score x is not a number".to_string()
+                },
+                ImportRowError {
+                    row: 3,
+                    message: "No place".to_string()
+                },
+                ImportRowError {
+                    row: 4,
+                    message: "No pilot".to_string()
+                },
+                ImportRowError {
+                    row: 5,
+                    message: "Gender ? is not F or M".to_string()
+                },
+                ImportRowError {
+                    row: 1,
+                    message: "Place 1 is taken more than once, mark ties with =".to_string()
+                },
+                ImportRowError {
+                    row: 2,
+                    message: "Place 1 is taken more than once, mark ties with =".to_string()
+                },
+                ImportRowError {
+                    row: 5,
+                    message: "No pilots placed 3 to 4".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_should_be_reported_by_line() {
+        let results = CsvImporter
+            .parse(
+                "",
+                &upload(
+                    "Place,Pin,First name,Last name
+
+1,1,Ann,Smith
+,,,
+
+,2,Bob,Jones",
+                ),
+            )
+            .unwrap();
+        assert_eq!(results.results[0].row, 2);
+        assert_eq!(
+            results.errors,
+            vec![ImportRowError {
+                row: 5,
+                message: "No place".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_should_need_place_and_pilot_columns() {
+        let error = CsvImporter
+            .parse("", &upload("Name,Score\nAnn,1"))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Choose the column with the places");
+        let error = CsvImporter
+            .parse("", &upload("Place,Score\n1,1"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Choose the columns with the pilots' pins or names"
+        );
+        assert!(CsvImporter.parse("", "1,Ann").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Result};
+
+use super::{Importer, SourceResult, SourceResults};
+use crate::data::fsdb_data_types::{FsdbCompetition, FsdbTask};
+use frontend::prs_data_types::{Competition, ImportRowError};
+
+/// An .fsdb file saved by FS, the FAI scoring program. It can only be uploaded
+pub struct FsdbImporter;
+
+impl Importer for FsdbImporter {
+    fn url(&self, _id: &str) -> Option<String> {
+        None
+    }
+
+    fn parse(&self, _id: &str, body: &str) -> Result<SourceResults> {
+        let fsdb = FsdbCompetition::parse(body)?;
+        let overall = fsdb
+            .overall_results()
+            .ok_or(anyhow!("No results in the file, publish them in FS first"))?;
+        let tasks: Vec<&FsdbTask> = fsdb.tasks.iter().filter(|task| !task.cancelled).collect();
+        let mut errors = vec![];
+        let results = overall
+            .placings
+            .iter()
+            .enumerate()
+            .filter_map(|(row, placing)| {
+                let participant = match fsdb.participant(&placing.participant_id) {
+                    Some(participant) => participant,
+                    None => {
+                        errors.push(ImportRowError {
+                            row,
+                            message: format!("No participant {}", placing.participant_id),
+                        });
+                        return None;
+                    }
+                };
+                if placing.points.is_none() {
+                    errors.push(ImportRowError {
+                        row,
+                        message: format!("No points for {}", participant.name),
+                    });
+                }
+                Some(SourceResult {
+                    row,
+                    source_id: Some(participant.civl_id.clone())
+                        .filter(|civl_id| !civl_id.is_empty())
+                        .unwrap_or(participant.id.clone()),
+                    name: participant.name.clone(),
+                    gender: participant.gender.clone(),
+                    rank: placing.rank.clone(),
+                    score: placing.points,
+                    task_scores: tasks
+                        .iter()
+                        .map(|task| task.points.get(&participant.id).copied())
+                        .collect(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Ok(SourceResults {
+            competition: Competition {
+                name: fsdb.name.clone(),
+                location: fsdb.location.clone(),
+                comp_date: fsdb.from.clone(),
+                num_tasks: tasks.len() as i64,
+                // Only when FS gave every task its validity
+                task_validity: tasks
+                    .iter()
+                    .map(|task| task.validity)
+                    .collect::<Option<Vec<f64>>>()
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+            results,
+            errors,
+            wprs: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fsdb_data_types::EXAMPLE_FSDB;
+
+    #[test]
+    fn parse_should_map_overall_results() {
+        let results = FsdbImporter.parse("", EXAMPLE_FSDB).unwrap();
+
+        assert_eq!(results.competition.name, "NZ Nationals & Open");
+        assert_eq!(results.competition.comp_date, "2023-01-05");
+        assert_eq!(results.competition.num_tasks, 1);
+        assert_eq!(results.competition.task_validity, vec![0.95]);
+        assert!(results.errors.is_empty());
+        assert_eq!(results.results.len(), 2);
+        assert_eq!(results.results[0].source_id, "12345");
+        assert_eq!(results.results[0].gender, "F");
+        assert_eq!(results.results[0].score, Some(950.0));
+        assert_eq!(results.results[0].task_scores, vec![Some(950.0)]);
+        assert_eq!(results.results[1].rank, "2");
+        assert_eq!(results.results[1].task_scores, vec![None]);
+    }
+
+    #[test]
+    fn parse_should_report_unknown_participants() {
+        let fsdb = EXAMPLE_FSDB.replace(
+            r#"<FsParticipant id="2" rank="2" points="0" />"#,
+            r#"<FsParticipant id="3" rank="2" points="0" />"#,
+        );
+        let results = FsdbImporter.parse("", &fsdb).unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(
+            results.errors,
+            vec![ImportRowError {
+                row: 1,
+                message: "No participant 3".to_string()
+            }]
+        );
+        let error = FsdbImporter
+            .parse("", "<Fs><FsCompetition name=\"Club day\" /></Fs>")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No results in the file, publish them in FS first"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+
+use super::{Importer, SourceResult, SourceResults};
+use crate::data::highcloud_data_types::HighCloudRoot;
+use frontend::prs_data_types::Competition;
+
+/// Points for a task of full validity, HighCloud scores a task out of 1000
+/// times its validity
+const TASK_MAX_SCORE: f64 = 1000.0;
+
+/// Results from HighCloud's `get_result.php`
+pub struct HighCloudImporter;
+
+impl Importer for HighCloudImporter {
+    fn url(&self, id: &str) -> Option<String> {
+        Some(format!(
+            "http://xc.highcloud.net/get_result.php?comPk={}&_=1678092363685",
+            id
+        ))
+    }
+
+    fn parse(&self, _id: &str, body: &str) -> Result<SourceResults> {
+        let highcloud_competition: HighCloudRoot =
+            serde_json::from_str(body).context("Not HighCloud results")?;
+        let tasks = highcloud_competition.task_count();
+        let (results, errors) = highcloud_competition.results();
+        let compinfo = highcloud_competition.compinfo;
+        Ok(SourceResults {
+            competition: Competition {
+                name: compinfo.com_name,
+                location: compinfo.com_location,
+                comp_date: compinfo.com_date_from,
+                num_tasks: tasks as i64,
+                // HighCloud only reports the summed validity, each task's
+                // validity is left for the scorer to enter
+                total_validity: Some(compinfo.total_validity as f64 / TASK_MAX_SCORE)
+                    .filter(|validity| *validity > 0.0),
+                ..Default::default()
+            },
+            results: results
+                .into_iter()
+                .map(|result| SourceResult {
+                    row: result.row,
+                    source_id: result.pin.clone(),
+                    pin: result.pin,
+                    name: result.name,
+                    gender: result.gender,
+                    rank: result.rank,
+                    score: result.total,
+                    task_scores: result.task_scores,
+                    ..Default::default()
+                })
+                .collect(),
+            errors,
+            wprs: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_should_keep_task_scores_and_errors() {
+        let mut body = serde_json::to_value(HighCloudRoot::default()).unwrap();
+        body["compinfo"]["comName"] = json!("Nationals");
+        body["compinfo"]["TotalValidity"] = json!(1800);
+        body["data"] = serde_json::from_str(
+            r#"[
+                [1, "1234", 7, "Ann Smith", "NZL", "F", "", "Zeno", "D", "", 950, 850],
+                [2],
+                ["=2", "", 9, "Cat Brown", "NZL", "F", "", "Rush", "B", "", "", 400]
+            ]"#,
+        )
+        .unwrap();
+
+        let results = HighCloudImporter.parse("358", &body.to_string()).unwrap();
+
+        assert_eq!(results.competition.name, "Nationals");
+        assert_eq!(results.competition.num_tasks, 2);
+        assert_eq!(results.competition.total_validity, Some(1.8));
+        assert!(results.competition.task_validity.is_empty());
+        assert_eq!(results.results.len(), 2);
+        assert_eq!(results.results[0].score, Some(1800.0));
+        assert_eq!(results.results[1].rank, "=2");
+        assert_eq!(results.results[1].task_scores, vec![None, Some(400.0)]);
+        assert_eq!(results.errors.len(), 1);
+        assert_eq!(results.errors[0].row, 1);
+        assert!(HighCloudImporter.parse("358", "<html>").is_err());
+    }
+}
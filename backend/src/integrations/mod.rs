@@ -0,0 +1,624 @@
+use anyhow::Result;
+use axum::{
+    extract::{DefaultBodyLimit, Path, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use frontend::prs_data_types::{
+    Competition, CompetitionKind, CompetitionPilot, ImportRowError, ImportedCompetition,
+    LinkedCompetition, Pilot, PilotMatch, PilotMatchKind, Placing, PlacingStatus, WprsDetails,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    data::{
+        data_access::get_text_external,
+        scoring_config::ScoringConfig,
+        storage::{storage_error, AppState},
+    },
+    scoring,
+};
+
+mod civl;
+mod csv;
+mod fsdb;
+mod highcloud;
+
+/// Largest results file that can be uploaded
+const UPLOAD_LIMIT: usize = 20 * 1024 * 1024;
+
+pub fn import_routes() -> Router<AppState> {
+    Router::new().route("/api/import/:source/:id", get(import_competition))
+}
+
+pub fn restricted_import_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/import/:source", post(upload_competition))
+        .route(
+            "/api/competition/:id/civl/:civl_id",
+            post(link_civl_competition),
+        )
+        .layer(DefaultBodyLimit::max(UPLOAD_LIMIT))
+}
+
+/// A results source competitions can be imported from
+pub trait Importer: Send + Sync {
+    /// Where the competition's results are fetched from, None for sources
+    /// whose results can only be uploaded
+    fn url(&self, id: &str) -> Option<String>;
+
+    /// Read the fetched results. Fails when they can't be read at all, rows
+    /// that can't be read are reported in the results
+    fn parse(&self, id: &str, body: &str) -> Result<SourceResults>;
+}
+
+/// Every importer by the source name used in the import route
+static IMPORTERS: [(&str, &dyn Importer); 4] = [
+    ("highcloud", &highcloud::HighCloudImporter),
+    ("civl", &civl::CivlImporter),
+    ("fsdb", &fsdb::FsdbImporter),
+    ("csv", &csv::CsvImporter),
+];
+
+pub fn importer(source: &str) -> Option<&'static dyn Importer> {
+    IMPORTERS
+        .iter()
+        .find(|(name, _)| *name == source)
+        .map(|(_, importer)| *importer)
+}
+
+/// A competition as read from a results source, before its pilots are matched
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SourceResults {
+    /// The draft competition, its placings are made from the results
+    pub competition: Competition,
+    pub results: Vec<SourceResult>,
+    pub errors: Vec<ImportRowError>,
+    pub wprs: Option<WprsDetails>,
+}
+
+/// A pilot's result as the source has it
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SourceResult {
+    /// The row it was read from, for reporting problems with it
+    pub row: usize,
+    /// The pilot's id in the source
+    pub source_id: String,
+    /// The pilot's NZ pin when the source has it
+    pub pin: String,
+    pub name: String,
+    pub gender: String,
+    /// As written, may be a tie or a status like "DNF"
+    pub rank: String,
+    pub score: Option<f64>,
+    pub fai_points: f64,
+    pub task_scores: Vec<Option<f64>>,
+}
+
+/// Fetch a competition from a results source and map it to a draft, matching
+/// pilots where possible
+async fn import_competition(
+    State(state): State<AppState>,
+    Path((source, id)): Path<(String, String)>,
+) -> Response {
+    let importer = match importer(&source) {
+        Some(importer) => importer,
+        None => {
+            return (StatusCode::NOT_FOUND, format!("No importer for {}", source)).into_response()
+        }
+    };
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return (StatusCode::BAD_REQUEST, "Invalid competition id").into_response();
+    }
+    let url = match importer.url(&id) {
+        Some(url) => url,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("{} results can only be uploaded", source),
+            )
+                .into_response()
+        }
+    };
+    let body = match get_text_external(url).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::NOT_FOUND).into_response(),
+    };
+    // The source has changed how it writes its results when they can't be read
+    read_results(
+        &state,
+        importer,
+        &source,
+        &id,
+        &body,
+        StatusCode::BAD_GATEWAY,
+    )
+}
+
+/// Read an uploaded results file and map it to a draft, matching pilots where
+/// possible
+async fn upload_competition(
+    State(state): State<AppState>,
+    Path(source): Path<String>,
+    body: String,
+) -> Response {
+    match importer(&source) {
+        Some(importer) => read_results(
+            &state,
+            importer,
+            &source,
+            "",
+            &body,
+            StatusCode::BAD_REQUEST,
+        ),
+        None => (StatusCode::NOT_FOUND, format!("No importer for {}", source)).into_response(),
+    }
+}
+
+fn read_results(
+    state: &AppState,
+    importer: &dyn Importer,
+    source: &str,
+    id: &str,
+    body: &str,
+    unreadable: StatusCode,
+) -> Response {
+    let (pilots, competitions) = match (state.storage.pilots(), state.storage.competitions()) {
+        (Ok(pilots), Ok(competitions)) => (pilots, competitions),
+        (Err(error), _) | (_, Err(error)) => return storage_error(error),
+    };
+    match importer.parse(id, body) {
+        Ok(results) => {
+            Json(map_results(results, &pilots, &competitions, &state.scoring)).into_response()
+        }
+        Err(error) => (
+            unreadable,
+            format!("Couldn't read the {} results: {}", source, error),
+        )
+            .into_response(),
+    }
+}
+
+/// Link a stored NZ competition to its WPRS results on civlcomps.org, filling
+/// each placing's WPRS points for the suggested exchange rate
+async fn link_civl_competition(
+    State(state): State<AppState>,
+    Path((id, civl_id)): Path<(String, String)>,
+) -> Response {
+    if civl_id.is_empty() || !civl_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return (StatusCode::BAD_REQUEST, "Invalid competition id").into_response();
+    }
+    let (competition, pilots) = match (state.storage.competition(&id), state.storage.pilots()) {
+        (Ok(Some(competition)), Ok(pilots)) => (competition, pilots),
+        (Ok(None), _) => return (StatusCode::NOT_FOUND).into_response(),
+        (Err(error), _) | (_, Err(error)) => return storage_error(error),
+    };
+    if competition.kind != CompetitionKind::Local {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Only NZ competitions can be linked to CIVL results",
+        )
+            .into_response();
+    }
+    let importer = civl::CivlImporter;
+    let body = match importer.url(&civl_id) {
+        Some(url) => match get_text_external(url).await {
+            Ok(body) => body,
+            Err(_) => return (StatusCode::NOT_FOUND).into_response(),
+        },
+        None => return (StatusCode::NOT_FOUND).into_response(),
+    };
+    let results = match importer.parse(&civl_id, &body) {
+        Ok(results) => results,
+        Err(error) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Couldn't read the civl results: {}", error),
+            )
+                .into_response()
+        }
+    };
+    let linked = link_civl_results(competition, results, &pilots);
+    match state.storage.save_competition(&linked.competition) {
+        Ok(_) => Json(linked).into_response(),
+        Err(error) => storage_error(error),
+    }
+}
+
+/// Set each placing's WPRS points from the competition's CIVL results and link
+/// the competition to them. Pilots are matched as on import, CIVL pilots
+/// without a placing are reported.
+pub fn link_civl_results(
+    competition: Competition,
+    results: SourceResults,
+    pilots: &[Pilot],
+) -> LinkedCompetition {
+    let mut competition = Competition {
+        civl_id: results.competition.civl_id,
+        ..competition
+    };
+    let mut errors = results.errors;
+    for placing in competition.placings.iter_mut() {
+        placing.fai_points = 0.0;
+    }
+    for result in &results.results {
+        let (pilot, _) = match_pilot(pilots, &result.pin, &result.name);
+        match pilot.and_then(|pilot| {
+            competition
+                .placings
+                .iter_mut()
+                .find(|placing| placing.pilot.pin == pilot.pin)
+        }) {
+            Some(placing) => placing.fai_points = result.fai_points,
+            None => errors.push(ImportRowError {
+                row: result.row,
+                message: format!("No placing for {}", result.name),
+            }),
+        }
+    }
+    errors.sort_by_key(|error| error.row);
+    LinkedCompetition {
+        competition,
+        errors,
+    }
+}
+
+/// Make the draft's placings from a source's results, matching each pilot to a
+/// stored pilot. Pins no stored pilot has are reported. Overseas competitions
+/// without an exchange rate get one suggested from the stored NZ competitions
+pub fn map_results(
+    results: SourceResults,
+    pilots: &[Pilot],
+    competitions: &[Competition],
+    config: &ScoringConfig,
+) -> ImportedCompetition {
+    let mut errors = results.errors;
+    let places = parse_places(results.results.iter().map(|r| r.rank.clone()));
+    let (placings, matches): (Vec<Placing>, Vec<PilotMatch>) = results
+        .results
+        .iter()
+        .zip(places)
+        .map(|(result, (place, status))| {
+            let (existing_pilot, kind) = match_pilot(pilots, &result.pin, &result.name);
+            if !result.pin.trim().is_empty() && kind != PilotMatchKind::Pin {
+                errors.push(ImportRowError {
+                    row: result.row,
+                    message: format!("Unknown pin {}", result.pin.trim()),
+                });
+            }
+            let mut split_name = result.name.split_whitespace();
+            let first_name = split_name.next().unwrap_or_default().to_string();
+            let last_name = split_name.last().unwrap_or_default().to_string();
+            let pilot = match existing_pilot {
+                Some(pilot) => CompetitionPilot {
+                    pin: pilot.pin.clone(),
+                    first_name: pilot.first_name.clone(),
+                    last_name: pilot.last_name.clone(),
+                    gender: Some(result.gender.clone())
+                        .filter(|gender| !gender.is_empty())
+                        .unwrap_or(pilot.gender.clone()),
+                },
+                None => CompetitionPilot {
+                    pin: "".to_string(),
+                    first_name,
+                    last_name,
+                    gender: result.gender.clone(),
+                },
+            };
+            let pilot_match = PilotMatch {
+                source_id: result.source_id.clone(),
+                name: result.name.clone(),
+                matched_pin: existing_pilot.map(|p| p.pin.clone()),
+                kind,
+            };
+            let placing = Placing {
+                pilot,
+                place,
+                status,
+                score: result.score,
+                fai_points: result.fai_points,
+                ..Default::default()
+            };
+            (placing, pilot_match)
+        })
+        .unzip();
+    let mut competition = Competition {
+        placings,
+        ..results.competition
+    };
+    if competition.kind == CompetitionKind::OverseasFai && competition.exchange_rate == 0.0 {
+        // Left at zero for the exchange rate to be entered by hand when there
+        // is nothing to base it on
+        competition.exchange_rate = competition
+            .comp_date
+            .parse()
+            .ok()
+            .and_then(|date| {
+                scoring::exchange_rate(&date, competition.discipline, competitions, config)
+                    .exchange_rate
+            })
+            .unwrap_or_default();
+    }
+    errors.sort_by_key(|error| error.row);
+    ImportedCompetition {
+        competition,
+        task_scores: results.results.into_iter().map(|r| r.task_scores).collect(),
+        errors,
+        wprs: results.wprs,
+        matches,
+    }
+}
+
+/// Read the places from a results table keeping ties. A tied place may be
+/// written as "=2" or "2=", or left blank after the first pilot on it. Pilots
+/// marked DNF, ABS, DSQ or WD get that status and no place.
+fn parse_places(values: impl Iterator<Item = String>) -> Vec<(i64, PlacingStatus)> {
+    values
+        .scan(0, |previous, value| {
+            if let Some(status) = PlacingStatus::from_code(&value) {
+                return Some((0, status));
+            }
+            if let Ok(place) = value.trim().trim_matches('=').parse::<i64>() {
+                *previous = place;
+            }
+            Some((*previous, PlacingStatus::Flew))
+        })
+        .collect()
+}
+
+/// Find a pilot searching by pin then name, names match ignoring case and
+/// hyphens
+fn match_pilot<'a>(
+    pilots: &'a [Pilot],
+    pin: &str,
+    fullname: &str,
+) -> (Option<&'a Pilot>, PilotMatchKind) {
+    let normalise = |name: &str| name.trim().replace('-', "").to_lowercase();
+    if let Some(pilot) = pilots
+        .iter()
+        .find(|p| !pin.is_empty() && p.pin == pin.trim())
+    {
+        return (Some(pilot), PilotMatchKind::Pin);
+    }
+    match pilots.iter().find(|p| {
+        normalise(&format!("{} {}", p.first_name.trim(), p.last_name.trim())) == normalise(fullname)
+    }) {
+        Some(pilot) => (Some(pilot), PilotMatchKind::Name),
+        None => (None, PilotMatchKind::Unmatched),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::data_access::load_data;
+    use chrono::NaiveDate;
+    use frontend::prs_data_types::Discipline;
+
+    #[tokio::test]
+    async fn import_from_highcloud_should_return_result() {
+        let result = import_competition(
+            State(load_data().unwrap().into()),
+            Path(("highcloud".to_string(), "358".to_string())),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn import_from_civl_works() {
+        let response = import_competition(
+            State(load_data().unwrap().into()),
+            Path(("civl".to_string(), "5859".to_string())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK)
+    }
+
+    #[tokio::test]
+    async fn import_should_reject_unknown_sources_and_ids() {
+        let response = import_competition(
+            State(load_data().unwrap().into()),
+            Path(("airscore".to_string(), "1".to_string())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = import_competition(
+            State(load_data().unwrap().into()),
+            Path(("civl".to_string(), "1&x=2".to_string())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn upload_should_read_fsdb_files() {
+        let response = upload_competition(
+            State(load_data().unwrap().into()),
+            Path("fsdb".to_string()),
+            crate::data::fsdb_data_types::EXAMPLE_FSDB.to_string(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = upload_competition(
+            State(load_data().unwrap().into()),
+            Path("fsdb".to_string()),
+            "not a results file".to_string(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = import_competition(
+            State(load_data().unwrap().into()),
+            Path(("fsdb".to_string(), "1".to_string())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn parse_places_should_keep_ties() {
+        let places = parse_places(
+            ["1", "2", "=2", "", "5=", "6", "DNF", "dsq"]
+                .iter()
+                .map(|p| p.to_string()),
+        );
+        assert_eq!(
+            places,
+            vec![
+                (1, PlacingStatus::Flew),
+                (2, PlacingStatus::Flew),
+                (2, PlacingStatus::Flew),
+                (2, PlacingStatus::Flew),
+                (5, PlacingStatus::Flew),
+                (6, PlacingStatus::Flew),
+                (0, PlacingStatus::DidNotFly),
+                (0, PlacingStatus::Disqualified)
+            ]
+        );
+    }
+
+    #[test]
+    fn map_results_should_report_matches() {
+        let pilots = vec![
+            Pilot {
+                pin: "1234".to_string(),
+                first_name: "Ann".to_string(),
+                last_name: "Smith".to_string(),
+                gender: "F".to_string(),
+                ..Default::default()
+            },
+            Pilot {
+                pin: "5678".to_string(),
+                first_name: "Bob".to_string(),
+                last_name: "Jones-Hill".to_string(),
+                gender: "M".to_string(),
+                ..Default::default()
+            },
+        ];
+        let result = |source_id: &str, pin: &str, name: &str, rank: &str| SourceResult {
+            source_id: source_id.to_string(),
+            pin: pin.to_string(),
+            name: name.to_string(),
+            rank: rank.to_string(),
+            ..Default::default()
+        };
+        let results = SourceResults {
+            results: vec![
+                result("a", "1234", "A Smith", "1"),
+                result("b", "", "bob joneshill", "2"),
+                result("c", "", "Cat Brown", "DNF"),
+            ],
+            ..Default::default()
+        };
+
+        let imported = map_results(results, &pilots, &[], &ScoringConfig::default());
+
+        let placings = &imported.competition.placings;
+        assert_eq!(placings[0].pilot.pin, "1234");
+        assert_eq!(placings[0].pilot.first_name, "Ann");
+        assert_eq!(placings[0].pilot.gender, "F");
+        assert_eq!(placings[1].pilot.pin, "5678");
+        assert_eq!(placings[2].pilot.pin, "");
+        assert_eq!(placings[2].pilot.last_name, "Brown");
+        assert_eq!(placings[2].status, PlacingStatus::DidNotFly);
+        assert_eq!(
+            imported.matches.iter().map(|m| m.kind).collect::<Vec<_>>(),
+            vec![
+                PilotMatchKind::Pin,
+                PilotMatchKind::Name,
+                PilotMatchKind::Unmatched
+            ]
+        );
+        assert_eq!(imported.matches[1].source_id, "b");
+        assert_eq!(imported.matches[1].matched_pin, Some("5678".to_string()));
+    }
+
+    #[test]
+    fn linked_competition_should_suggest_exchange_rate() {
+        let pilot = |pin: &str, first_name: &str, last_name: &str| Pilot {
+            pin: pin.to_string(),
+            first_name: first_name.to_string(),
+            last_name: last_name.to_string(),
+            ..Default::default()
+        };
+        let pilots = vec![pilot("1001", "Ann", "Smith"), pilot("1002", "Bob", "Jones")];
+        let placing = |pin: &str, place: i64| Placing {
+            pilot: CompetitionPilot {
+                pin: pin.to_string(),
+                ..Default::default()
+            },
+            place,
+            ..Default::default()
+        };
+        let config = ScoringConfig::default();
+        let competition = scoring::recalculate_competition(
+            &Competition {
+                id: "2022-06-01-Bright".to_string(),
+                comp_date: "2022-06-01".to_string(),
+                num_tasks: 5,
+                placings: vec![placing("1001", 1), placing("1002", 2)],
+                ..Default::default()
+            },
+            None,
+            &vec![],
+            &config,
+        )
+        .unwrap();
+        let result = |row: usize, name: &str, fai_points: f64| SourceResult {
+            row,
+            name: name.to_string(),
+            fai_points,
+            ..Default::default()
+        };
+        let results = SourceResults {
+            competition: Competition {
+                civl_id: Some("5859".to_string()),
+                ..Default::default()
+            },
+            results: vec![
+                result(3, "Ann Smith", 40.0),
+                result(7, "Bob Jones", 20.0),
+                result(9, "Cat Brown", 10.0),
+            ],
+            ..Default::default()
+        };
+
+        let linked = link_civl_results(competition, results, &pilots);
+
+        assert_eq!(linked.competition.civl_id, Some("5859".to_string()));
+        let fai_points: Vec<f64> = linked
+            .competition
+            .placings
+            .iter()
+            .map(|p| p.fai_points)
+            .collect();
+        assert_eq!(fai_points, vec![40.0, 20.0]);
+        assert_eq!(linked.errors.len(), 1);
+        assert_eq!(linked.errors[0].row, 9);
+        let nzprs_average = linked
+            .competition
+            .placings
+            .iter()
+            .map(|p| p.points)
+            .sum::<f64>()
+            / 2.0;
+        let rate = scoring::exchange_rate(
+            &NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &[linked.competition],
+            &config,
+        );
+        assert!(nzprs_average > 0.0);
+        assert_eq!(rate.exchange_rate, Some(nzprs_average / 30.0));
+    }
+
+    #[test]
+    fn importers_should_be_found_by_source() {
+        assert!(importer("highcloud").is_some());
+        assert!(importer("civl").is_some());
+        assert!(importer("fsdb").is_some_and(|fsdb| fsdb.url("1").is_none()));
+        assert!(importer("csv").is_some_and(|csv| csv.url("1").is_none()));
+        assert!(importer("fai").is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use admin::restricted_admin_routes;
 use axum::{
     http::StatusCode,
     middleware,
@@ -6,11 +7,11 @@ use axum::{
     Extension, Json, Router,
 };
 use competitions::{competition_routes, restricted_competition_routes};
-use data::data_access::*;
+use data::storage::{open_storage, AppState};
 use frontend::prs_data_types::UserInfo;
 use google_auth::google_auth;
 use google_signin::CachedCerts;
-use integrations::{from_fai, from_highcloud};
+use integrations::{import_routes, restricted_import_routes};
 use opentelemetry::sdk::trace::{self};
 use opentelemetry::{
     global::{self},
@@ -18,7 +19,7 @@ use opentelemetry::{
     KeyValue,
 };
 use opentelemetry_otlp::WithExportConfig;
-use pilots::pilot_routes;
+use pilots::{pilot_routes, restricted_pilot_routes};
 use rankings::ranking_routes;
 use std::{collections::HashMap, env, net::SocketAddr, path::PathBuf};
 use tokio::signal;
@@ -27,42 +28,47 @@ use tracing::instrument::WithSubscriber;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{filter, Layer};
+mod admin;
+mod comparison;
 mod competitions;
 mod data;
 mod google_auth;
 mod integrations;
 mod pilots;
+mod publishing;
 mod rankings;
 mod scoring;
+mod simulation;
 
 async fn get_profile(Extension(profile): Extension<UserInfo>) -> Response {
     (StatusCode::OK, Json(profile)).into_response()
 }
 
-fn setup_server() -> Router {
+fn setup_server(state: AppState) -> Router {
     let assets_dir = PathBuf::from("./dist");
     let static_files_service = get_service(
         tower_http::services::ServeDir::new(assets_dir)
             .append_index_html_on_directories(true)
             .fallback(ServeFile::new("./dist/index.html")),
     );
-    let data = load_data().unwrap();
-    let admin_users: Vec<String> = data.admin_users.iter().map(|f| f.clone()).collect();
+    let admin_users: Vec<String> = state.storage.admin_users().unwrap();
     let google_certs = CachedCerts::new();
     Router::new()
         .fallback(static_files_service)
         .route("/api/profile", get(get_profile))
         .merge(restricted_competition_routes())
+        .merge(restricted_admin_routes())
+        .merge(restricted_pilot_routes())
+        .merge(restricted_import_routes())
         .route_layer(middleware::from_fn_with_state(
             (admin_users.clone(), google_certs.clone()),
             google_auth,
         ))
-        .route("/api/competition/fromhc/:compid", get(from_highcloud))
-        .route("/api/competition/fromfai/:compid", get(from_fai))
+        .merge(import_routes())
         .merge(competition_routes())
         .merge(pilot_routes())
         .merge(ranking_routes())
-        .with_state(data)
+        .with_state(state)
         .layer(CatchPanicLayer::new())
         .layer(TraceLayer::new_for_http())
 }
@@ -137,7 +143,11 @@ async fn main() {
         tracing_subscriber::registry().with(layer).init();
     }
 
-    let router = setup_server();
+    let state = open_storage().unwrap();
+    if publishing::monthly_publishing_enabled() {
+        publishing::spawn_monthly_publisher(state.clone());
+    }
+    let router = setup_server(state);
     // run our app with hyper
     let addr = SocketAddr::from(([0, 0, 0, 0], 8000));
     tracing::info!("nzprs backend listening on {}", addr);
@@ -155,6 +165,6 @@ mod tests {
 
     #[test]
     fn server_should_be_valid() {
-        let _ = setup_server();
+        let _ = setup_server(open_storage().unwrap());
     }
 }
@@ -1,44 +1,170 @@
 use axum::{
-    extract::{self, Path, State},
+    extract::{self, Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
 
-use frontend::prs_data_types::{Competition, Root};
+use chrono::{Months, NaiveDate};
+use frontend::prs_data_types::{Competition, Discipline, Pilot, Ranking};
 
-pub fn pilot_routes() -> Router<Root> {
+use crate::{
+    data::storage::{storage_error, AppState},
+    publishing::{current_ranking_month, ranking_month},
+    rankings::DisciplineQuery,
+    scoring,
+};
+
+pub fn pilot_routes() -> Router<AppState> {
     Router::new()
         .route("/api/pilots", get(pilots))
         .route("/api/pilot/:pin", get(pilot))
         .route("/api/pilot/:pin/competitions", get(pilot_competitions))
+        .route("/api/pilot/:pin/explain/:date", get(explain_points))
+        .route("/api/pilot/:pin/history", get(pilot_history))
+}
+
+pub fn restricted_pilot_routes() -> Router<AppState> {
+    Router::new().route("/api/pilots", post(save_pilot))
+}
+
+async fn pilots(State(state): State<AppState>) -> Response {
+    match state.storage.pilots() {
+        Ok(pilots) => (StatusCode::OK, Json(pilots)).into_response(),
+        Err(error) => storage_error(error),
+    }
+}
+
+async fn pilot(State(state): State<AppState>, Path(pin): extract::Path<i64>) -> Response {
+    match state.storage.pilot(&pin.to_string()) {
+        Ok(Some(pilot)) => (StatusCode::OK, Json(pilot)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND).into_response(),
+        Err(error) => storage_error(error),
+    }
+}
+
+/// Add a pilot or update the registry details of an existing one
+async fn save_pilot(State(state): State<AppState>, Json(pilot): extract::Json<Pilot>) -> Response {
+    if pilot.pin.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "A pilot needs a pin").into_response();
+    }
+    match state.storage.save_pilot(&pilot) {
+        Ok(_) => Json(pilot).into_response(),
+        Err(error) => storage_error(error),
+    }
 }
 
-async fn pilots(State(data): State<Root>) -> Response {
-    (StatusCode::OK, Json(data.pilots)).into_response()
+async fn pilot_competitions(
+    State(state): State<AppState>,
+    Path(pin): extract::Path<i32>,
+) -> Response {
+    match state.storage.pilot_competitions(&pin.to_string()) {
+        Ok(competitions) => Json(competitions).into_response(),
+        Err(error) => storage_error(error),
+    }
+}
+
+/// Every result behind the pilot's ranking points on the date and the factors
+/// that make up each one. Pilots are found by their placings so those missing
+/// from the registry can still be explained.
+async fn explain_points(
+    State(state): State<AppState>,
+    Path((pin, date)): extract::Path<(i64, String)>,
+    Query(query): Query<DisciplineQuery>,
+) -> Response {
+    let date = match date.parse::<NaiveDate>() {
+        Ok(date) => date,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+    };
+    let pin = pin.to_string();
+    let competitions = match state.storage.pilot_competitions(&pin) {
+        Ok(competitions) if competitions.is_empty() => {
+            return (StatusCode::NOT_FOUND).into_response()
+        }
+        Ok(competitions) => competitions,
+        Err(error) => return storage_error(error),
+    };
+    match scoring::explain_points(&pin, &date, query.discipline, &competitions, &state.scoring) {
+        Some(explanation) => Json(explanation).into_response(),
+        None => (StatusCode::BAD_REQUEST).into_response(),
+    }
 }
 
-async fn pilot(State(data): State<Root>, Path(pin): extract::Path<i64>) -> Response {
-    match data.pilots.iter().find(|p| p.pin == pin.to_string()) {
-        Some(pilot) => (StatusCode::OK, Json(pilot.clone())).into_response(),
-        None => (StatusCode::NOT_FOUND).into_response(),
+/// Ranking dates from the pilot's first competition in the discipline up to
+/// `until`: every stored ranking, then each month after the latest one. Only
+/// the months not yet published have to be calculated.
+fn history_dates(
+    competitions: &[Competition],
+    rankings: &[Ranking],
+    discipline: Discipline,
+    until: NaiveDate,
+) -> Vec<NaiveDate> {
+    let first = competitions
+        .iter()
+        .filter(|c| c.discipline == discipline)
+        .filter_map(|c| c.comp_date.parse::<NaiveDate>().ok())
+        .min();
+    let first = match first {
+        Some(first) => first,
+        None => return vec![],
+    };
+    let mut dates: Vec<NaiveDate> = rankings
+        .iter()
+        .filter(|r| r.discipline == discipline && r.category.is_none())
+        .filter_map(|r| r.date.parse::<NaiveDate>().ok())
+        .filter(|date| first.lt(date) && date.le(&until))
+        .collect();
+    let latest = dates.iter().max().copied().unwrap_or(first).max(first);
+    let mut month = ranking_month(latest).checked_add_months(Months::new(1));
+    while let Some(date) = month.filter(|date| date.le(&until)) {
+        dates.push(date);
+        month = date.checked_add_months(Months::new(1));
     }
+    dates.sort();
+    dates.dedup();
+    dates
 }
 
-async fn pilot_competitions(State(data): State<Root>, Path(pin): extract::Path<i32>) -> Response {
-    Json(
-        &data
-            .competitions
-            .iter()
-            .filter(|c| {
-                c.placings
-                    .iter()
-                    .any(|placing| placing.pilot.pin == pin.to_string())
-            })
-            .map(|c| c.clone())
-            .collect::<Vec<Competition>>(),
-    )
+/// The pilot's position and points in every ranking since their first
+/// competition in the discipline
+async fn pilot_history(
+    State(state): State<AppState>,
+    Path(pin): extract::Path<i64>,
+    Query(query): Query<DisciplineQuery>,
+) -> Response {
+    let pin = pin.to_string();
+    match state.storage.pilot(&pin) {
+        Ok(Some(_)) => (),
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(error) => return storage_error(error),
+    };
+    let (pilot_competitions, competitions, rankings) = match (
+        state.storage.pilot_competitions(&pin),
+        state.storage.competitions(),
+        state.storage.rankings(),
+    ) {
+        (Ok(pilot_competitions), Ok(competitions), Ok(rankings)) => {
+            (pilot_competitions, competitions, rankings)
+        }
+        (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => {
+            return storage_error(error)
+        }
+    };
+    let dates = history_dates(
+        &pilot_competitions,
+        &rankings,
+        query.discipline,
+        current_ranking_month(),
+    );
+    Json(scoring::pilot_history(
+        &pin,
+        query.discipline,
+        &dates,
+        &competitions,
+        &rankings,
+        &state.scoring,
+    ))
     .into_response()
 }
 
@@ -46,22 +172,143 @@ async fn pilot_competitions(State(data): State<Root>, Path(pin): extract::Path<i
 mod tests {
     use super::*;
     use crate::data::data_access::load_data;
+    use frontend::prs_data_types::{CompetitionPilot, Placing, Root};
 
     #[tokio::test]
     async fn pilot_competitions_should_return_result() {
-        let result = pilot_competitions(State(load_data().unwrap()), Path(5410)).await;
+        let result = pilot_competitions(State(load_data().unwrap().into()), Path(5410)).await;
         assert_eq!(result.status(), StatusCode::OK);
     }
 
     #[tokio::test]
     async fn pilots_should_return_result() {
-        let result = pilots(State(load_data().unwrap())).await;
+        let result = pilots(State(load_data().unwrap().into())).await;
         assert_eq!(result.status(), StatusCode::OK);
     }
 
     #[tokio::test]
     async fn pilot_should_return_result() {
-        let result = pilot(State(load_data().unwrap()), Path(5410)).await;
+        let result = pilot(State(load_data().unwrap().into()), Path(5410)).await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn saved_pilot_should_be_returned() {
+        let state: AppState = Root::default().into();
+        let result = save_pilot(
+            State(state.clone()),
+            Json(Pilot {
+                pin: "1001".to_string(),
+                birth_year: Some(2001),
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            state.storage.pilot("1001").unwrap().unwrap().birth_year,
+            Some(2001)
+        );
+        let result = save_pilot(State(state), Json(Pilot::default())).await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn history_dates_should_be_monthly_from_first_competition() {
+        let competitions = vec![Competition {
+            comp_date: "2022-01-10".to_string(),
+            ..Default::default()
+        }];
+        let rankings = vec![Ranking {
+            date: "2022-02-15".to_string(),
+            ..Default::default()
+        }];
+        let dates = history_dates(
+            &competitions,
+            &rankings,
+            Discipline::ParaglidingXc,
+            NaiveDate::from_ymd_opt(2022, 4, 1).unwrap(),
+        );
+        assert_eq!(
+            dates.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+            vec!["2022-02-15", "2022-03-01", "2022-04-01"]
+        );
+        assert_eq!(
+            history_dates(
+                &competitions,
+                &[],
+                Discipline::ParaglidingXc,
+                NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(),
+            ),
+            vec![
+                NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2022, 3, 1).unwrap()
+            ]
+        );
+        assert!(history_dates(
+            &competitions,
+            &rankings,
+            Discipline::Accuracy,
+            NaiveDate::from_ymd_opt(2022, 4, 1).unwrap(),
+        )
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn pilot_history_should_return_result() {
+        let result = pilot_history(
+            State(load_data().unwrap().into()),
+            Path(5410),
+            Query(DisciplineQuery::default()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn explain_points_should_find_unregistered_pilots() {
+        let competition = Competition {
+            id: "2022-01-10-Wanaka".to_string(),
+            comp_date: "2022-01-10".to_string(),
+            placings: vec![Placing {
+                pilot: CompetitionPilot {
+                    pin: "1001".to_string(),
+                    ..Default::default()
+                },
+                place: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let state: AppState = Root {
+            competitions: vec![competition],
+            ..Default::default()
+        }
+        .into();
+        let result = explain_points(
+            State(state.clone()),
+            Path((1001, "2022-02-01".to_string())),
+            Query(DisciplineQuery::default()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = explain_points(
+            State(state),
+            Path((1002, "2022-02-01".to_string())),
+            Query(DisciplineQuery::default()),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn explain_points_should_return_result() {
+        let result = explain_points(
+            State(load_data().unwrap().into()),
+            Path((5410, "2023-01-01".to_string())),
+            Query(DisciplineQuery::default()),
+        )
+        .await;
         assert_eq!(result.status(), StatusCode::OK);
     }
 }
@@ -0,0 +1,244 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use chrono_tz::{Pacific::Auckland, Tz};
+use frontend::prs_data_types::{Discipline, PublishStatus, PublishedRanking};
+use std::{env, time::Duration};
+
+use crate::{data::storage::AppState, scoring};
+
+/// First day of the month the date falls in
+pub fn ranking_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// The current ranking month in New Zealand
+pub fn current_ranking_month() -> NaiveDate {
+    ranking_month(Utc::now().with_timezone(&Auckland).date_naive())
+}
+
+/// Disciplines with at least one competition, these are the ones published
+fn published_disciplines(state: &AppState) -> Result<Vec<Discipline>> {
+    let competitions = state.storage.competitions()?;
+    Ok(Discipline::ALL
+        .into_iter()
+        .filter(|d| competitions.iter().any(|c| c.discipline == *d))
+        .collect())
+}
+
+/// Calculate and store the discipline's ranking for the month of `date`. A
+/// month that has already been published is left alone unless `force` is set.
+pub fn publish_ranking(
+    state: &AppState,
+    date: NaiveDate,
+    discipline: Discipline,
+    force: bool,
+) -> Result<PublishedRanking> {
+    let storage = state.storage.as_ref();
+    let date = ranking_month(date).format("%Y-%m-%d").to_string();
+    let existing = storage.ranking(&date, discipline)?;
+    if let (Some(existing), false) = (&existing, force) {
+        return Ok(PublishedRanking {
+            date,
+            discipline,
+            status: PublishStatus::Skipped,
+            pilots: existing.ranking_points.len(),
+        });
+    }
+    let ranking = scoring::assemble_ranking(
+        &date.parse::<NaiveDate>()?,
+        discipline,
+        &storage.competitions()?,
+        &storage.pilots()?,
+        &storage.rankings()?,
+        &state.scoring,
+    )
+    .ok_or(anyhow!("Unable to calculate the ranking for {}", date))?;
+    storage.save_ranking(&ranking)?;
+    tracing::info!("Published {} ranking {}", discipline.name(), date);
+    Ok(PublishedRanking {
+        date,
+        discipline,
+        status: match existing {
+            Some(_) => PublishStatus::Replaced,
+            None => PublishStatus::Published,
+        },
+        pilots: ranking.ranking_points.len(),
+    })
+}
+
+/// Publish every month from `from` to `to` inclusive, oldest first, for each
+/// discipline with competitions
+pub fn backfill_rankings(
+    state: &AppState,
+    from: NaiveDate,
+    to: NaiveDate,
+    force: bool,
+) -> Result<Vec<PublishedRanking>> {
+    let disciplines = published_disciplines(state)?;
+    let mut month = ranking_month(from);
+    let mut published = Vec::new();
+    while month <= to {
+        for discipline in &disciplines {
+            published.push(publish_ranking(state, month, *discipline, force)?);
+        }
+        month = month
+            .checked_add_months(Months::new(1))
+            .ok_or(anyhow!("Date out of range"))?;
+    }
+    Ok(published)
+}
+
+/// Five past midnight on the first of the month after `now`, NZ time
+fn next_publish_time(now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let next_month = ranking_month(now.date_naive()).checked_add_months(Months::new(1))?;
+    Auckland
+        .from_local_datetime(&next_month.and_hms_opt(0, 5, 0)?)
+        .earliest()
+}
+
+/// Whether rankings are published monthly, set `NZPRS_PUBLISH_MONTHLY=true`
+/// to turn it on. Off by default so a development data file isn't changed
+/// just by starting the server.
+pub fn monthly_publishing_enabled() -> bool {
+    env::var("NZPRS_PUBLISH_MONTHLY").is_ok_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+/// Publish the current month straight away where it is missing, then publish
+/// each new month as it starts
+pub fn spawn_monthly_publisher(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            let month = current_ranking_month();
+            // Storage is written synchronously so it is kept off the runtime's
+            // workers
+            let publishing = state.clone();
+            let published = tokio::task::spawn_blocking(move || {
+                let _writing = publishing.writes.blocking_lock();
+                backfill_rankings(&publishing, month, month, false)
+            })
+            .await;
+            match published {
+                Ok(Ok(published)) => tracing::debug!("Monthly rankings {:?}", published),
+                Ok(Err(error)) => tracing::error!("Failed to publish monthly rankings: {}", error),
+                Err(error) => tracing::error!("Monthly publishing stopped: {}", error),
+            }
+            let now = Utc::now().with_timezone(&Auckland);
+            let wait = next_publish_time(now)
+                .and_then(|next| next.signed_duration_since(now).to_std().ok())
+                .unwrap_or(Duration::from_secs(60 * 60));
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::scoring_config::ScoringConfig;
+    use frontend::prs_data_types::{Competition, CompetitionPilot, Pilot, Placing, Root};
+    use serde_json::json;
+
+    fn test_state() -> AppState {
+        let competition = Competition {
+            id: "2022-01-10-Wanaka".to_string(),
+            name: "Wanaka".to_string(),
+            comp_date: "2022-01-10".to_string(),
+            num_tasks: 5,
+            pq: json!(1.0),
+            placings: vec![Placing {
+                pilot: CompetitionPilot {
+                    pin: "1001".to_string(),
+                    ..Default::default()
+                },
+                place: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let competition = scoring::recalculate_competition(
+            &competition,
+            None,
+            &vec![],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        Root {
+            pilots: vec![Pilot {
+                pin: "1001".to_string(),
+                first_name: "First".to_string(),
+                last_name: "Pilot".to_string(),
+                gender: "FEMALE".to_string(),
+                ..Default::default()
+            }],
+            competitions: vec![competition],
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn publish_ranking_should_be_idempotent() -> Result<()> {
+        let state = test_state();
+        let date = NaiveDate::from_ymd_opt(2022, 2, 14).unwrap();
+        let first = publish_ranking(&state, date, Discipline::ParaglidingXc, false)?;
+        let second = publish_ranking(&state, date, Discipline::ParaglidingXc, false)?;
+        let forced = publish_ranking(&state, date, Discipline::ParaglidingXc, true)?;
+
+        assert_eq!(first.status, PublishStatus::Published);
+        assert_eq!(first.date, "2022-02-01");
+        assert_eq!(second.status, PublishStatus::Skipped);
+        assert_eq!(forced.status, PublishStatus::Replaced);
+        let ranking = state
+            .storage
+            .ranking("2022-02-01", Discipline::ParaglidingXc)?
+            .unwrap();
+        assert_eq!(ranking.ranking_points[0].pilot_first_name, "First");
+        assert_eq!(ranking.ranking_points[0].pilot_last_name, "Pilot");
+        assert_eq!(
+            ranking.ranking_points[0].pilot_gender,
+            Some("FEMALE".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn backfill_should_publish_missing_months() -> Result<()> {
+        let state = test_state();
+        publish_ranking(
+            &state,
+            NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            false,
+        )?;
+        let published = backfill_rankings(
+            &state,
+            NaiveDate::from_ymd_opt(2022, 1, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2022, 4, 1).unwrap(),
+            false,
+        )?;
+        assert_eq!(
+            published
+                .iter()
+                .map(|p| p.status.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                PublishStatus::Published,
+                PublishStatus::Skipped,
+                PublishStatus::Published,
+                PublishStatus::Published
+            ]
+        );
+        assert_eq!(state.storage.rankings()?.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn next_publish_time_should_be_first_of_next_month() {
+        let now = Auckland.with_ymd_and_hms(2022, 12, 31, 23, 0, 0).unwrap();
+        let next = next_publish_time(now).unwrap();
+        assert_eq!(
+            next,
+            Auckland.with_ymd_and_hms(2023, 1, 1, 0, 5, 0).unwrap()
+        );
+    }
+}
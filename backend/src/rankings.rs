@@ -1,50 +1,136 @@
 use axum::{
-    extract::{self, Path, State},
+    extract::{self, Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::get,
     Json, Router,
 };
 use chrono::NaiveDate;
-use frontend::prs_data_types::Root;
+use frontend::prs_data_types::Discipline;
+use serde::Deserialize;
 
-use crate::scoring;
+use crate::{
+    comparison,
+    data::storage::{storage_error, AppState},
+    scoring,
+};
 
-pub fn ranking_routes() -> Router<Root> {
+pub fn ranking_routes() -> Router<AppState> {
     Router::new()
         .route("/api/rankings", get(get_rankings))
         .route("/api/ranking/:date", get(get_ranking))
-        .route("/api/rankings/:date", post(create_ranking))
+        .route("/api/categories", get(get_categories))
+        .route("/api/rankings/compare", get(compare_rankings))
+}
+
+/// The discipline a request is for, paragliding XC when not given
+#[derive(Debug, Default, Deserialize)]
+pub struct DisciplineQuery {
+    #[serde(default)]
+    pub discipline: Discipline,
+}
+
+async fn get_rankings(
+    State(state): State<AppState>,
+    Query(query): Query<DisciplineQuery>,
+) -> Response {
+    match state.storage.rankings() {
+        Ok(mut rankings) => {
+            rankings.retain(|r| r.discipline == query.discipline);
+            Json(rankings).into_response()
+        }
+        Err(error) => storage_error(error),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RankingQuery {
+    #[serde(default)]
+    discipline: Discipline,
+    category: Option<String>,
 }
 
-async fn get_rankings(State(data): State<Root>) -> Response {
-    Json(data.rankings).into_response()
+/// The ranking for the date, or with `?category=` the ranking derived from it
+/// for that category
+async fn get_ranking(
+    State(state): State<AppState>,
+    Path(date): extract::Path<String>,
+    Query(query): Query<RankingQuery>,
+) -> Response {
+    let category = match query
+        .category
+        .map(|id| state.scoring.category(&id).cloned())
+    {
+        Some(Some(category)) => Some(category),
+        Some(None) => return (StatusCode::BAD_REQUEST, "Unknown category").into_response(),
+        None => None,
+    };
+    let ranking = match state.storage.ranking(&date, query.discipline) {
+        Ok(Some(ranking)) => ranking,
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(error) => return storage_error(error),
+    };
+    let category = match category {
+        Some(category) => category,
+        None => return (StatusCode::OK, Json(ranking)).into_response(),
+    };
+    let (pilots, rankings) = match (state.storage.pilots(), state.storage.rankings()) {
+        (Ok(pilots), Ok(rankings)) => (pilots, rankings),
+        (Err(error), _) | (_, Err(error)) => return storage_error(error),
+    };
+    let previous = match ranking.date.parse::<NaiveDate>() {
+        Ok(date) => scoring::previous_ranking(&date, query.discipline, &rankings),
+        Err(_) => None,
+    };
+    match scoring::category_ranking(&ranking, &category, &pilots, previous) {
+        Some(ranking) => (StatusCode::OK, Json(ranking)).into_response(),
+        None => (StatusCode::BAD_REQUEST).into_response(),
+    }
+}
+
+async fn get_categories(State(state): State<AppState>) -> Response {
+    Json(&state.scoring.categories).into_response()
 }
 
-async fn get_ranking(State(data): State<Root>, Path(date): extract::Path<String>) -> Response {
-    (
-        StatusCode::OK,
-        Json(
-            data.rankings
-                .iter()
-                .find(|r| r.date.cmp(&date).is_eq())
-                .unwrap(),
-        ),
-    )
-        .into_response()
+#[derive(Debug, Deserialize)]
+struct CompareQuery {
+    from: String,
+    to: String,
+    #[serde(default)]
+    discipline: Discipline,
 }
 
-async fn create_ranking(State(data): State<Root>, Path(date): extract::Path<String>) -> Response {
-    let date = date.parse::<NaiveDate>();
-    match date {
-        Ok(date) => {
-            let results = scoring::calculate_rankings(&date, &data.competitions);
-            match results {
-                Some(results) => Json(results).into_response(),
+/// What changed between the published rankings on two dates
+async fn compare_rankings(
+    State(state): State<AppState>,
+    Query(query): Query<CompareQuery>,
+) -> Response {
+    match (
+        query.from.parse::<NaiveDate>(),
+        query.to.parse::<NaiveDate>(),
+    ) {
+        (Ok(from), Ok(to)) if from < to => (),
+        (Ok(_), Ok(_)) => {
+            return (StatusCode::BAD_REQUEST, "from must be before to").into_response()
+        }
+        _ => return (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+    };
+    let (from, to) = match (
+        state.storage.ranking(&query.from, query.discipline),
+        state.storage.ranking(&query.to, query.discipline),
+    ) {
+        (Ok(Some(from)), Ok(Some(to))) => (from, to),
+        (Ok(_), Ok(_)) => return (StatusCode::NOT_FOUND).into_response(),
+        (Err(error), _) | (_, Err(error)) => return storage_error(error),
+    };
+    match state.storage.competitions() {
+        Ok(competitions) => {
+            match comparison::compare_rankings(&from, &to, &competitions, &state.scoring) {
+                Some(comparison) => Json(comparison).into_response(),
                 None => (StatusCode::BAD_REQUEST).into_response(),
             }
         }
-        Err(_) => (StatusCode::BAD_REQUEST, "Not a valid date").into_response(),
+        Err(error) => storage_error(error),
     }
 }
 
@@ -56,26 +142,83 @@ mod tests {
 
     #[tokio::test]
     async fn rankings_should_return_result() {
-        let result = get_rankings(State(load_data().unwrap())).await;
+        let result = get_rankings(
+            State(load_data().unwrap().into()),
+            Query(DisciplineQuery::default()),
+        )
+        .await;
         assert_eq!(result.status(), StatusCode::OK);
     }
 
     #[tokio::test]
     async fn ranking_should_return_result() {
-        let result = get_ranking(State(load_data().unwrap()), Path("2019-01-01".to_string())).await;
+        let result = get_ranking(
+            State(load_data().unwrap().into()),
+            Path("2019-01-01".to_string()),
+            Query(RankingQuery::default()),
+        )
+        .await;
         assert_eq!(result.status(), StatusCode::OK);
     }
 
     #[tokio::test]
-    async fn create_valid_ranking_should_return_result() {
-        let result =
-            create_ranking(State(load_data().unwrap()), Path("2022-01-01".to_string())).await;
+    async fn category_ranking_should_return_result() {
+        let query = |category: &str| {
+            Query(RankingQuery {
+                category: Some(category.to_string()),
+                ..Default::default()
+            })
+        };
+        let result = get_ranking(
+            State(load_data().unwrap().into()),
+            Path("2019-01-01".to_string()),
+            query("women"),
+        )
+        .await;
         assert_eq!(result.status(), StatusCode::OK);
+        let result = get_ranking(
+            State(load_data().unwrap().into()),
+            Path("2019-01-01".to_string()),
+            query("under-5s"),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn create_invalid_ranking_should_return_bad_request() {
-        let result = create_ranking(State(load_data().unwrap()), Path("2022".to_string())).await;
+    async fn compare_rankings_should_return_result() {
+        let query = |from: &str, to: &str| {
+            Query(CompareQuery {
+                from: from.to_string(),
+                to: to.to_string(),
+                discipline: Discipline::ParaglidingXc,
+            })
+        };
+        let result = compare_rankings(
+            State(load_data().unwrap().into()),
+            query("2019-01-01", "2019-02-01"),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = compare_rankings(
+            State(load_data().unwrap().into()),
+            query("2019-02-01", "2019-01-01"),
+        )
+        .await;
         assert_eq!(result.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn ranking_in_other_discipline_should_return_not_found() {
+        let result = get_ranking(
+            State(load_data().unwrap().into()),
+            Path("2019-01-01".to_string()),
+            Query(RankingQuery {
+                discipline: Discipline::HangGlidingXc,
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
 }
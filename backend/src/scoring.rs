@@ -1,6 +1,10 @@
-use crate::data::constants::*;
+use crate::data::scoring_config::{ScoringConfig, ScoringRules};
 use frontend::prs_data_types::{
-    CompResult, Competition, CompetitionPilot, Placing, Ranking, RankingPoint,
+    CompResult, Competition, CompetitionChange, CompetitionForecast, CompetitionKind,
+    CompetitionPilot, Discipline, ExchangeRate, ExchangeRateInput, ForecastAddition,
+    ForecastRequest, Pilot, PilotRankingHistory, Placing, PointsExplanation, Ranking,
+    RankingCategory, RankingChange, RankingPoint, RecalculationSummary, ResultExplanation,
+    ResultStatus,
 };
 
 use chrono::prelude::*;
@@ -8,26 +12,37 @@ use chrono::Months;
 use serde_json::json;
 use std::collections::HashMap;
 
+/// Pilots counted as taking part, see `PlacingStatus`
+fn participants(competition: &Competition) -> usize {
+    competition
+        .placings
+        .iter()
+        .filter(|p| p.status.is_participant())
+        .count()
+}
+
 fn participant_number(
     current_competition: &Competition,
     competition_history: &Vec<Competition>,
+    rules: &ScoringRules,
 ) -> Option<f64> {
     // Get the date 2 years prior to the comp date
     let this_comp_date = current_competition.comp_date.parse::<NaiveDate>().ok()?;
-    let two_years_earlier = this_comp_date.checked_sub_months(Months::new(24))?;
+    let two_years_earlier = this_comp_date.checked_sub_months(Months::new(rules.pn_months))?;
     // Number of participants in this comp
-    let num_participants = current_competition.placings.len() as f64;
+    let num_participants = participants(current_competition) as f64;
 
     // Calc the average num participants in the last 24 months
     let previous_competition_placings: Vec<f64> = competition_history
         .iter()
         .map(|previous_competition| {
             if let Ok(other_comp_date) = previous_competition.comp_date.parse::<NaiveDate>() {
-                if !previous_competition.overseas // Exclude Overseas comps from the average because we just want the average num pilots at NZ comps
+                if !previous_competition.overseas() // Exclude Overseas comps from the average because we just want the average num pilots at NZ comps
+                        && previous_competition.discipline == current_competition.discipline
                         && other_comp_date.lt(&this_comp_date)
                         && other_comp_date.gt(&two_years_earlier)
                 {
-                    return Some(previous_competition.placings.len() as f64);
+                    return Some(participants(previous_competition) as f64);
                 }
             }
             None
@@ -39,34 +54,56 @@ fn participant_number(
         + num_participants)
         / previous_competition_count;
     let raw_pn = (num_participants / ave_num_participants).sqrt();
-    Some(raw_pn.min(constants::PN_MAX))
+    Some(raw_pn.min(rules.pn_max))
 }
 
-/// Update all calculated properties for a comptition
+/// Update all calculated properties for a comptition using the rules in force
+/// on the competition date
 pub fn recalculate_competition(
     competition: &Competition,
     ranking: Option<&Ranking>,
     comps: &Vec<Competition>,
+    config: &ScoringConfig,
 ) -> Option<Competition> {
+    let rules = config.rules_for(&competition.comp_date.parse::<NaiveDate>().ok()?);
+    let competition = &match competition.kind {
+        CompetitionKind::OverseasLeague => Competition {
+            placings: league_placings(&competition.placings),
+            ..competition.clone()
+        },
+        _ => competition.clone(),
+    };
     let mut updated_competition = competition.clone();
-    let pq = pilot_quality(ranking, &competition.placings);
+    let participant_placings: Vec<Placing> = competition
+        .placings
+        .iter()
+        .filter(|p| p.status.is_participant())
+        .cloned()
+        .collect();
+    let pq = pilot_quality(ranking, &participant_placings, rules);
     updated_competition.pq = json!(pq);
-    updated_competition.pn = participant_number(competition, comps)?;
+    updated_competition.pn = participant_number(competition, comps, rules)?;
+    updated_competition.ta = rules.competition_task_quality(competition);
+    let winner_score = competition
+        .placings
+        .iter()
+        .filter(|p| p.status.earns_points())
+        .filter_map(|p| p.score)
+        .fold(0.0, f64::max);
     let mut max_points = 0.0;
     for mut placing in updated_competition.placings.iter_mut() {
-        if competition.overseas {
+        if !placing.status.earns_points() {
+            placing.pplacing = 0.0;
+            placing.pp = 0.0;
+            placing.points = 0.0;
+        } else if competition.kind == CompetitionKind::OverseasFai {
             placing.points = placing.fai_points * competition.exchange_rate;
         } else {
-            placing.pplacing = calculate_pilot_placing(competition, placing.place as f64);
-            placing.pp = placing
-                .pplacing
-                .powf(1.0 + pq)
-                .max(placing.pplacing.powf(2.0));
-            placing.points = placing.pp
-                * pq
-                * updated_competition.pn
-                * competition_task_quality(competition.num_tasks as u8)
-                * 100.0;
+            placing.pplacing = calculate_pilot_placing(competition, placing.place);
+            let factor = rules.placing_factor(placing.pplacing, placing.score, winner_score);
+            placing.pp = factor.powf(1.0 + pq).max(factor.powf(2.0));
+            placing.points =
+                placing.pp * pq * updated_competition.pn * updated_competition.ta * 100.0;
         }
         max_points = placing.points.max(max_points);
     }
@@ -74,12 +111,53 @@ pub fn recalculate_competition(
     Some(updated_competition)
 }
 
-fn calculate_pilot_placing(competition: &Competition, place: f64) -> f64 {
-    let last_place = competition.placings.len() as f64;
-    (last_place - place + 1.0) / last_place
+/// Place the NZ pilots from an overseas result 1..n in their order in the
+/// results, keeping ties. Pilots who did not take part are left as they are.
+fn league_placings(placings: &[Placing]) -> Vec<Placing> {
+    let mut participants: Vec<Placing> = placings
+        .iter()
+        .filter(|p| p.status.is_participant())
+        .cloned()
+        .collect();
+    participants.sort_by_key(|p| p.place);
+    let mut previous: Option<(i64, i64)> = None;
+    participants
+        .into_iter()
+        .enumerate()
+        .map(|(i, placing)| {
+            let place = match previous {
+                Some((result_place, place)) if result_place == placing.place => place,
+                _ => i as i64 + 1,
+            };
+            previous = Some((placing.place, place));
+            Placing { place, ..placing }
+        })
+        .chain(
+            placings
+                .iter()
+                .filter(|p| !p.status.is_participant())
+                .cloned(),
+        )
+        .collect()
+}
+
+/// Pilots tied on a place share the average Pplacing of the places they
+/// cover, so with places 1, 2, 2, 4 both second placed pilots get the average
+/// of 2nd and 3rd. Disqualified pilots finish last.
+fn calculate_pilot_placing(competition: &Competition, place: i64) -> f64 {
+    let last_place = participants(competition) as f64;
+    let flown = || {
+        competition
+            .placings
+            .iter()
+            .filter(|p| p.status.earns_points())
+    };
+    let ahead = flown().filter(|p| p.place < place).count() as f64;
+    let tied = flown().filter(|p| p.place == place).count().max(1) as f64;
+    (last_place - ahead - (tied - 1.0) / 2.0) / last_place
 }
 
-fn pilot_quality(ranking: Option<&Ranking>, placings: &Vec<Placing>) -> f64 {
+fn pilot_quality(ranking: Option<&Ranking>, placings: &Vec<Placing>, rules: &ScoringRules) -> f64 {
     match ranking {
         None => 1.0,
         Some(ranking) => {
@@ -93,9 +171,9 @@ fn pilot_quality(ranking: Option<&Ranking>, placings: &Vec<Placing>) -> f64 {
                 ranking.ranking_points.clone(),
             );
             if pq_srp == 0.0 || pq_srtp == 0.0 {
-                return (1.0 - constants::PQ_MIN) + constants::PQ_MIN;
+                return (1.0 - rules.pq_min) + rules.pq_min;
             }
-            pq_srp / pq_srtp * (1.0 - constants::PQ_MIN) + constants::PQ_MIN
+            pq_srp / pq_srtp * (1.0 - rules.pq_min) + rules.pq_min
         }
     }
 }
@@ -123,96 +201,410 @@ fn pilot_quality_srp(pilots: Vec<CompetitionPilot>, ranking_points: Vec<RankingP
         .sum()
 }
 
-/// Get the quality of the competition
-fn competition_task_quality(number_of_tasks: u8) -> f64 {
-    match number_of_tasks {
-        0 => 0.0,
-        1 => 0.4,
-        2 => 0.6,
-        3 => 0.8,
-        4 => 0.9,
-        _ => 1.0,
+/// Get the competition decay factor
+fn competition_decay(days_since_competition: f64, rules: &ScoringRules) -> f64 {
+    let n = days_since_competition / rules.td_period * rules.td_b - rules.td_b / 2.0;
+    1.0 / (1.0 + rules.td_a.powf(n))
+}
+
+/// Whether the competition falls between the window start and the ranking date
+pub fn in_ranking_window(
+    competition: &Competition,
+    three_years_earlier: &NaiveDate,
+    ranking_date: &NaiveDate,
+) -> bool {
+    match competition.comp_date.parse::<NaiveDate>() {
+        Ok(date) => date.gt(&three_years_earlier) && date.lt(ranking_date),
+        _ => false,
     }
 }
-/// Get the competition decay factor
-fn competition_decay(days_since_competition: f64) -> f64 {
-    let n = days_since_competition / constants::TD_PERIOD * constants::TD_B - constants::TD_B / 2.0;
-    1.0 / (1.0 + constants::TD_A.powf(n))
+
+/// Sort a pilot's results, most valuable first, and mark the ones that count:
+/// the best results once overseas results beyond the cap are dropped
+pub fn select_results(
+    mut results: Vec<CompResult>,
+    rules: &ScoringRules,
+) -> Vec<(CompResult, ResultStatus)> {
+    results.sort_by(|a, b| b.points.total_cmp(&a.points));
+    let mut overseas = 0;
+    let mut counted = 0;
+    results
+        .into_iter()
+        .map(|result| {
+            if result.overseas {
+                overseas += 1;
+                if overseas > rules.max_overseas_results {
+                    return (result, ResultStatus::OverseasCap);
+                }
+            }
+            if counted < rules.best_results {
+                counted += 1;
+                (result, ResultStatus::Counted)
+            } else {
+                (result, ResultStatus::NotInBest)
+            }
+        })
+        .collect()
 }
-/// Calculate the decayed rankings for a date given past competition results
+
+/// Calculate the decayed rankings in a discipline for a date given past
+/// competition results, using the rules in force on the ranking date
 pub fn calculate_rankings(
     ranking_date: &NaiveDate,
+    discipline: Discipline,
     competitions: &Vec<Competition>,
+    config: &ScoringConfig,
 ) -> Option<Vec<RankingPoint>> {
+    let rules = config.rules_for(ranking_date);
     // Get the date 3 years prior to the ranking date
-    let three_years_earlier = ranking_date.checked_sub_months(Months::new(36))?;
-    let mut rankings: Vec<RankingPoint> = competitions
+    let three_years_earlier = ranking_date.checked_sub_months(Months::new(rules.ranking_months))?;
+    let ranked_competitions: Vec<&Competition> = competitions
         .iter()
-        // Cycle through each comp within the last 3 years
-        .filter(|c| {
-            let other_comp_date = c.comp_date.parse::<NaiveDate>();
-            match other_comp_date {
-                Ok(date) => date.gt(&three_years_earlier) && date.lt(ranking_date),
-                _ => false,
-            }
-        })
+        // Cycle through each comp in the discipline within the last 3 years
+        .filter(|c| c.discipline == discipline)
+        .filter(|c| in_ranking_window(c, &three_years_earlier, ranking_date))
+        .collect();
+    // Names and gender as entered on the pilot's placings
+    let placing_pilots: HashMap<&str, &CompetitionPilot> = ranked_competitions
+        .iter()
+        .flat_map(|c| c.placings.iter())
+        .map(|p| (p.pilot.pin.as_str(), &p.pilot))
+        .collect();
+    let mut rankings: Vec<RankingPoint> = ranked_competitions
+        .into_iter()
         .flat_map(|competition| {
             competition
                 .placings
                 .iter()
-                .map(|placing| time_decayed_points(competition, placing, ranking_date))
+                .map(|placing| time_decayed_points(competition, placing, ranking_date, rules))
                 .flatten()
         })
         .fold(
             HashMap::new(),
             |mut pin_results: HashMap<String, Vec<CompResult>>,
              pin_result: (String, CompResult)| {
-                match pin_results.get_mut(&pin_result.0) {
-                    Some(results) => {
-                        results.push(pin_result.1.clone());
-                        results.sort_by(|a, b| b.points.total_cmp(&a.points));
-                        remove_extra_overseas(results);
-                    }
-                    None => {
-                        pin_results.insert(pin_result.0, [pin_result.1.clone()].to_vec());
-                    }
-                };
+                pin_results
+                    .entry(pin_result.0)
+                    .or_default()
+                    .push(pin_result.1);
                 pin_results
             },
         )
-        .iter()
-        .map(|pin_results| RankingPoint {
-            pilot_first_name: pin_results.0.clone(),
-            pilot_gender: None,
-            pilot_last_name: pin_results.0.clone(),
-            pilot_pin: pin_results.0.clone(),
-            results: pin_results.1.clone(),
-            total_points: pin_results.1.iter().take(4).map(|r| r.points).sum(),
+        .into_iter()
+        .map(|(pin, results)| {
+            let selected = select_results(results, rules);
+            let pilot = placing_pilots.get(pin.as_str());
+            let name = |name: Option<&String>| match name {
+                Some(name) if !name.trim().is_empty() => name.clone(),
+                _ => pin.clone(),
+            };
+            RankingPoint {
+                pilot_first_name: name(pilot.map(|p| &p.first_name)),
+                pilot_last_name: name(pilot.map(|p| &p.last_name)),
+                pilot_gender: pilot
+                    .map(|p| p.gender.clone())
+                    .filter(|g| !g.trim().is_empty()),
+                pilot_pin: pin.clone(),
+                total_points: selected
+                    .iter()
+                    .filter(|(_, status)| *status == ResultStatus::Counted)
+                    .map(|(r, _)| r.points)
+                    .sum(),
+                results: selected
+                    .into_iter()
+                    .filter(|(_, status)| *status != ResultStatus::OverseasCap)
+                    .map(|(r, _)| r)
+                    .collect(),
+                ..Default::default()
+            }
         })
         .collect();
     rankings.sort_by(|a, b| b.total_points.total_cmp(&a.total_points));
+    assign_positions(&mut rankings);
     Some(rankings)
 }
 
-/// Remove any extra overseas competitions keeping the most valuable 2
-fn remove_extra_overseas(results: &mut Vec<CompResult>) {
-    let overseas_results: Vec<&CompResult> = results.iter().filter(|r| r.overseas).collect();
-    if overseas_results.len() > 2 {
-        if let Some(position) = results
-            .iter()
-            .position(|f| f.comp_id == overseas_results.last().unwrap().comp_id)
-        {
-            results.remove(position);
-        }
+/// Number sorted ranking points by position, equal totals share a position
+/// and the next one skips, 1, 2, 2, 4
+fn assign_positions(ranking_points: &mut [RankingPoint]) {
+    for i in 0..ranking_points.len() {
+        ranking_points[i].position = match i {
+            0 => 1,
+            _ if !is_different(
+                ranking_points[i].total_points,
+                ranking_points[i - 1].total_points,
+            ) =>
+            {
+                ranking_points[i - 1].position
+            }
+            _ => i + 1,
+        };
     }
 }
 
-/// Calculate the devalued points for the competitions
+/// Put together the ranking in the discipline for a date: the calculated
+/// points with pilot names and gender from the registry where the pilot is in
+/// it, and each pilot's position in the previous month's ranking
+pub fn assemble_ranking(
+    ranking_date: &NaiveDate,
+    discipline: Discipline,
+    competitions: &Vec<Competition>,
+    pilots: &[Pilot],
+    rankings: &[Ranking],
+    config: &ScoringConfig,
+) -> Option<Ranking> {
+    let date = ranking_date.format("%Y-%m-%d").to_string();
+    let ranking_points = calculate_rankings(ranking_date, discipline, competitions, config)?
+        .into_iter()
+        .map(|mut point| {
+            if let Some(pilot) = pilots.iter().find(|p| p.pin == point.pilot_pin) {
+                point.pilot_first_name = pilot.first_name.clone();
+                point.pilot_last_name = pilot.last_name.clone();
+                point.pilot_gender = Some(pilot.gender.clone());
+            }
+            point
+        })
+        .collect();
+    Some(Ranking {
+        id: date.clone(),
+        date,
+        discipline,
+        category: None,
+        ranking_points: with_movement(
+            ranking_points,
+            previous_ranking(ranking_date, discipline, rankings),
+        ),
+    })
+}
+
+/// The latest ranking in the discipline in the month before the date
+pub fn previous_ranking<'a>(
+    ranking_date: &NaiveDate,
+    discipline: Discipline,
+    rankings: &'a [Ranking],
+) -> Option<&'a Ranking> {
+    let month_earlier = ranking_date.checked_sub_months(Months::new(1))?;
+    rankings
+        .iter()
+        .filter(|r| r.discipline == discipline && r.category.is_none())
+        .filter(|r| match r.date.parse::<NaiveDate>() {
+            Ok(date) => month_earlier.le(&date) && date.lt(ranking_date),
+            Err(_) => false,
+        })
+        .max_by(|a, b| a.date.cmp(&b.date))
+}
+
+/// The ranking's points in order with their positions, worked out for rankings
+/// stored before positions were kept
+pub fn positioned_points(ranking: &Ranking) -> Vec<RankingPoint> {
+    let mut ranking_points = ranking.ranking_points.clone();
+    if ranking_points.iter().any(|p| p.position == 0) {
+        ranking_points.sort_by(|a, b| b.total_points.total_cmp(&a.total_points));
+        assign_positions(&mut ranking_points);
+    }
+    ranking_points
+}
+
+/// Record each pilot's position in the previous ranking and how many places
+/// they have moved up since, pilots new to the ranking have neither
+fn with_movement(
+    ranking_points: Vec<RankingPoint>,
+    previous: Option<&Ranking>,
+) -> Vec<RankingPoint> {
+    let previous_points = previous.map_or(vec![], positioned_points);
+    ranking_points
+        .into_iter()
+        .map(|point| {
+            let previous_position = previous_points
+                .iter()
+                .find(|p| p.pilot_pin == point.pilot_pin)
+                .map(|p| p.position);
+            RankingPoint {
+                previous_position,
+                movement: previous_position.map(|p| p as i64 - point.position as i64),
+                ..point
+            }
+        })
+        .collect()
+}
+
+/// Derive the ranking for a category from an overall ranking, keeping the
+/// order and sharing positions as the overall ranking does. Gender and birth
+/// year come from the pilot registry, falling back to the gender on the
+/// ranking for pilots not in it. Movement is against the category ranking
+/// derived from `previous`.
+pub fn category_ranking(
+    ranking: &Ranking,
+    category: &RankingCategory,
+    pilots: &[Pilot],
+    previous: Option<&Ranking>,
+) -> Option<Ranking> {
+    let year = ranking.date.parse::<NaiveDate>().ok()?.year();
+    let mut ranking_points: Vec<RankingPoint> = ranking
+        .ranking_points
+        .iter()
+        .filter(|point| {
+            let pilot = pilots.iter().find(|p| p.pin == point.pilot_pin);
+            let gender = pilot
+                .map(|p| p.gender.as_str())
+                .or(point.pilot_gender.as_deref());
+            let age = pilot.and_then(|p| p.birth_year).map(|born| year - born);
+            in_category(category, gender, age)
+        })
+        .cloned()
+        .collect();
+    assign_positions(&mut ranking_points);
+    let previous = previous.and_then(|r| category_ranking(r, category, pilots, None));
+    Some(Ranking {
+        id: ranking.id.clone(),
+        date: ranking.date.clone(),
+        discipline: ranking.discipline,
+        category: Some(category.id.clone()),
+        ranking_points: with_movement(ranking_points, previous.as_ref()),
+    })
+}
+
+fn in_category(category: &RankingCategory, gender: Option<&str>, age: Option<i32>) -> bool {
+    let gender_matches = match (&category.gender, gender) {
+        (None, _) => true,
+        (Some(required), Some(gender)) => required.eq_ignore_ascii_case(gender.trim()),
+        (Some(_), None) => false,
+    };
+    let age_matches = match (category.min_age, category.max_age, age) {
+        (None, None, _) => true,
+        (_, _, None) => false,
+        (min_age, max_age, Some(age)) => {
+            min_age.map_or(true, |min_age| age >= min_age)
+                && max_age.map_or(true, |max_age| age <= max_age)
+        }
+    };
+    gender_matches && age_matches
+}
+
+/// Break a pilot's ranking total in the discipline on the date down into the
+/// factors behind each result, as set out in the Readme
+pub fn explain_points(
+    pin: &str,
+    ranking_date: &NaiveDate,
+    discipline: Discipline,
+    competitions: &[Competition],
+    config: &ScoringConfig,
+) -> Option<PointsExplanation> {
+    let rules = config.rules_for(ranking_date);
+    // Get the date 3 years prior to the ranking date
+    let three_years_earlier = ranking_date.checked_sub_months(Months::new(rules.ranking_months))?;
+    let pilot_results: Vec<(&Competition, &Placing, CompResult)> = competitions
+        .iter()
+        .filter(|c| c.discipline == discipline)
+        .filter(|c| in_ranking_window(c, &three_years_earlier, ranking_date))
+        .flat_map(|competition| {
+            competition
+                .placings
+                .iter()
+                .filter(|placing| placing.pilot.pin == pin)
+                .filter_map(move |placing| {
+                    let (_, result) =
+                        time_decayed_points(competition, placing, ranking_date, rules)?;
+                    Some((competition, placing, result))
+                })
+        })
+        .collect();
+    let selected = select_results(
+        pilot_results.iter().map(|(_, _, r)| r.clone()).collect(),
+        rules,
+    );
+    let results: Vec<ResultExplanation> = selected
+        .into_iter()
+        .filter_map(|(result, status)| {
+            let (competition, placing, _) = pilot_results
+                .iter()
+                .find(|(c, _, _)| c.id == result.comp_id)?;
+            let comp_date = competition.comp_date.parse::<NaiveDate>().ok()?;
+            let days_since_competition =
+                ranking_date.signed_duration_since(comp_date).num_days() as f64;
+            Some(ResultExplanation {
+                comp_id: competition.id.clone(),
+                comp_name: competition.name.clone(),
+                comp_date: competition.comp_date.clone(),
+                place: placing.place,
+                overseas: competition.kind == CompetitionKind::OverseasFai,
+                pplacing: placing.pplacing,
+                pp: placing.pp,
+                pq: competition.pq.as_f64().unwrap_or_default(),
+                pn: competition.pn,
+                ta: competition.ta,
+                fai_points: placing.fai_points,
+                exchange_rate: competition.exchange_rate,
+                points: placing.points,
+                td: competition_decay(days_since_competition, rules),
+                decayed_points: result.points,
+                status,
+            })
+        })
+        .collect();
+    Some(PointsExplanation {
+        pilot_pin: pin.to_string(),
+        ranking_date: ranking_date.format("%Y-%m-%d").to_string(),
+        rules_version: rules.version.clone(),
+        total_points: results
+            .iter()
+            .filter(|r| r.status == ResultStatus::Counted)
+            .map(|r| r.decayed_points)
+            .sum(),
+        results,
+    })
+}
+
+/// The pilot's place in the discipline's ranking on each of the dates, from
+/// the stored ranking where there is one and calculated otherwise
+pub fn pilot_history(
+    pin: &str,
+    discipline: Discipline,
+    dates: &[NaiveDate],
+    competitions: &Vec<Competition>,
+    rankings: &[Ranking],
+    config: &ScoringConfig,
+) -> Vec<PilotRankingHistory> {
+    dates
+        .iter()
+        .filter_map(|date| {
+            let date_string = date.format("%Y-%m-%d").to_string();
+            let ranking_points = match rankings.iter().find(|r| {
+                r.date == date_string && r.discipline == discipline && r.category.is_none()
+            }) {
+                Some(ranking) => positioned_points(ranking),
+                None => calculate_rankings(date, discipline, competitions, config)?,
+            };
+            let point = ranking_points.into_iter().find(|p| p.pilot_pin == pin);
+            let counted_results: Vec<CompResult> = match &point {
+                Some(point) => select_results(point.results.clone(), config.rules_for(date))
+                    .into_iter()
+                    .filter(|(_, status)| *status == ResultStatus::Counted)
+                    .map(|(result, _)| result)
+                    .collect(),
+                None => vec![],
+            };
+            Some(PilotRankingHistory {
+                date: date_string,
+                position: point.as_ref().map(|p| p.position),
+                total_points: point.map_or(0.0, |p| p.total_points),
+                counted_results,
+            })
+        })
+        .collect()
+}
+
+/// Calculate the devalued points for the competitions. Only placings that earn
+/// points give a ranking result.
 fn time_decayed_points(
     competition: &Competition,
     placing: &Placing,
     ranking_date: &NaiveDate,
+    rules: &ScoringRules,
 ) -> Option<(String, CompResult)> {
+    if !placing.status.earns_points() {
+        return None;
+    }
     let comp_date = competition.comp_date.parse::<NaiveDate>().ok()?;
     let days_since_competition = ranking_date.signed_duration_since(comp_date).num_days() as f64;
     Some((
@@ -221,68 +613,511 @@ fn time_decayed_points(
             place: placing.place.clone(),
             comp_id: competition.id.clone(),
             comp_name: competition.name.clone(),
-            points: placing.points * competition_decay(days_since_competition),
-            overseas: competition.overseas.clone(),
+            points: placing.points * competition_decay(days_since_competition, rules),
+            overseas: competition.kind == CompetitionKind::OverseasFai,
         },
     ))
 }
 
-#[cfg(test)]
-mod tests {
-    use anyhow::Result;
-    use serde_json::json;
+/// Ranked pilots considered as additions to a forecast entry list
+const FORECAST_TOP_PILOTS: usize = 20;
 
-    use super::*;
-    use crate::data::data_access;
-    use frontend::prs_data_types::Pilot;
+/// Forecast a planned competition's value from its entry list. Pq and Pn only
+/// depend on who enters, so the winner's points are known before it flies,
+/// depending only on how many tasks are flown.
+pub fn forecast_competition(
+    request: &ForecastRequest,
+    competitions: &Vec<Competition>,
+    rankings: &[Ranking],
+    config: &ScoringConfig,
+) -> Option<CompetitionForecast> {
+    let comp_date = request.comp_date.parse::<NaiveDate>().ok()?;
+    let rules = config.rules_for(&comp_date);
+    let draft = |pins: &[String]| Competition {
+        comp_date: request.comp_date.clone(),
+        num_tasks: request.num_tasks,
+        discipline: request.discipline,
+        placings: pins
+            .iter()
+            .map(|pin| Placing {
+                pilot: CompetitionPilot {
+                    pin: pin.clone(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let entered = draft(&request.pins);
+    let ranking = prior_ranking(&entered, rankings, config);
+    let ta_min = rules.task_quality(request.discipline, 1);
+    let ta_max = rules.task_quality(request.discipline, request.num_tasks as usize);
+    // The winner's Pp is 1 so their points are the competition's value
+    let value = |competition: &Competition| -> Option<(f64, f64)> {
+        let pq = pilot_quality(ranking, &competition.placings, rules);
+        let pn = participant_number(competition, competitions, rules)?;
+        Some((pq, pn))
+    };
+    let (pq, pn) = value(&entered)?;
+    let winner_points = pq * pn * ta_max * 100.0;
+    let mut additions: Vec<ForecastAddition> = ranking
+        .map_or(vec![], positioned_points)
+        .into_iter()
+        .filter(|point| point.position <= FORECAST_TOP_PILOTS)
+        .filter(|point| !request.pins.contains(&point.pilot_pin))
+        .filter_map(|point| {
+            let mut pins = request.pins.clone();
+            pins.push(point.pilot_pin.clone());
+            let (pq, pn) = value(&draft(&pins))?;
+            Some(ForecastAddition {
+                value_increase: pq * pn * ta_max * 100.0 - winner_points,
+                pilot_pin: point.pilot_pin,
+                pilot_first_name: point.pilot_first_name,
+                pilot_last_name: point.pilot_last_name,
+                position: point.position,
+            })
+        })
+        .collect();
+    additions.sort_by(|a, b| b.value_increase.total_cmp(&a.value_increase));
+    Some(CompetitionForecast {
+        pq,
+        pn,
+        ta_min,
+        ta_max,
+        winner_points,
+        winner_points_min: pq * pn * ta_min * 100.0,
+        additions,
+    })
+}
 
-    #[test]
-    fn recalculate_comp_should_get_number() -> Result<()> {
-        let mut root = data_access::load_data()?;
-        root.competitions
-            .sort_by(|a, b| b.comp_date.cmp(&a.comp_date));
-        for competition in root.competitions.clone() {
-            let comp_val = recalculate_competition(
-                &competition,
-                root.rankings.iter().find(|r| {
-                    let comp_date = competition.comp_date.parse::<NaiveDate>().unwrap();
-                    let rdate = &&r.date.parse::<NaiveDate>().unwrap();
-                    let two_years_earlier = comp_date.checked_sub_months(Months::new(24)).unwrap();
-                    two_years_earlier.lt(&rdate) && (comp_date.gt(&rdate) || comp_date.eq(&rdate))
-                }),
-                &root.competitions,
-            );
-            if let Some(comp) = comp_val {
-                if (comp.comp_value - competition.comp_value).abs() > 0.00000001 {
-                    println!(
-                        "{} | {} - {}",
-                        competition.name, comp.comp_value, competition.comp_value
-                    );
+/// The most recent ranking in the competition's discipline on or before the
+/// competition date and within the rules' Pq ranking months prior to it. This
+/// is the ranking used for the competition's Pq.
+pub fn prior_ranking<'a>(
+    competition: &Competition,
+    rankings: &'a [Ranking],
+    config: &ScoringConfig,
+) -> Option<&'a Ranking> {
+    let comp_date = competition.comp_date.parse::<NaiveDate>().ok()?;
+    let months = config.rules_for(&comp_date).pq_ranking_months;
+    let earliest = comp_date.checked_sub_months(Months::new(months))?;
+    rankings
+        .iter()
+        .filter(|r| r.discipline == competition.discipline)
+        .filter(|r| match r.date.parse::<NaiveDate>() {
+            Ok(rdate) => earliest.lt(&rdate) && rdate.le(&comp_date),
+            Err(_) => false,
+        })
+        .max_by(|a, b| a.date.cmp(&b.date))
+}
+
+/// The suggested exchange rate for an overseas competition in the discipline on
+/// the date. This is the average NZPRS score of NZ competitions in the
+/// discipline in the rules' exchange rate months before that were also WPRS
+/// events, divided by their average WPRS score. Only pilots with WPRS points
+/// are compared.
+pub fn exchange_rate(
+    date: &NaiveDate,
+    discipline: Discipline,
+    competitions: &[Competition],
+    config: &ScoringConfig,
+) -> ExchangeRate {
+    let months = config.rules_for(date).exchange_rate_months;
+    let earliest = date
+        .checked_sub_months(Months::new(months))
+        .unwrap_or(NaiveDate::MIN);
+    let inputs: Vec<ExchangeRateInput> = competitions
+        .iter()
+        .filter(|c| {
+            c.kind == CompetitionKind::Local
+                && c.discipline == discipline
+                && in_ranking_window(c, &earliest, date)
+        })
+        .filter_map(|competition| {
+            let civl_id = competition.civl_id.clone()?;
+            let placings: Vec<&Placing> = competition
+                .placings
+                .iter()
+                .filter(|p| p.status.earns_points() && p.fai_points > 0.0)
+                .collect();
+            if placings.is_empty() {
+                return None;
+            }
+            let pilots = placings.len() as f64;
+            Some(ExchangeRateInput {
+                id: competition.id.clone(),
+                name: competition.name.clone(),
+                comp_date: competition.comp_date.clone(),
+                civl_id,
+                pilots: placings.len(),
+                nzprs_average: placings.iter().map(|p| p.points).sum::<f64>() / pilots,
+                wprs_average: placings.iter().map(|p| p.fai_points).sum::<f64>() / pilots,
+            })
+        })
+        .collect();
+    let count = inputs.len() as f64;
+    let nzprs_average = inputs.iter().map(|i| i.nzprs_average).sum::<f64>() / count.max(1.0);
+    let wprs_average = inputs.iter().map(|i| i.wprs_average).sum::<f64>() / count.max(1.0);
+    ExchangeRate {
+        date: date.format("%Y-%m-%d").to_string(),
+        exchange_rate: match wprs_average > 0.0 {
+            true => Some(nzprs_average / wprs_average),
+            false => None,
+        },
+        nzprs_average,
+        wprs_average,
+        competitions: inputs,
+    }
+}
+
+#[derive(Clone)]
+enum CompetitionOrRanking<'a> {
+    Ranking(&'a Ranking),
+    Competition(&'a Competition),
+}
+
+/// Every competition and ranking in chronological order. Rankings on the same
+/// date as a competition come first as that is the ranking the competition is
+/// scored against.
+fn chronological_events<'a>(
+    competitions: &'a [Competition],
+    rankings: &'a [Ranking],
+) -> Vec<CompetitionOrRanking<'a>> {
+    let mut events: Vec<(&str, CompetitionOrRanking)> = rankings
+        .iter()
+        .map(|r| (r.date.as_str(), CompetitionOrRanking::Ranking(r)))
+        .chain(
+            competitions
+                .iter()
+                .map(|c| (c.comp_date.as_str(), CompetitionOrRanking::Competition(c))),
+        )
+        .collect();
+    events.sort_by(|a, b| {
+        a.0.cmp(b.0).then_with(|| match (&a.1, &b.1) {
+            (CompetitionOrRanking::Ranking(_), CompetitionOrRanking::Competition(_)) => {
+                std::cmp::Ordering::Less
+            }
+            (CompetitionOrRanking::Competition(_), CompetitionOrRanking::Ranking(_)) => {
+                std::cmp::Ordering::Greater
+            }
+            _ => std::cmp::Ordering::Equal,
+        })
+    });
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+/// The ranking's points recalculated from the competitions and rankings
+/// replayed before it
+fn recalculate_ranking(
+    ranking: &Ranking,
+    competitions: &Vec<Competition>,
+    pilots: &[Pilot],
+    rankings: &[Ranking],
+    config: &ScoringConfig,
+) -> Ranking {
+    let mut new_ranking = ranking.clone();
+    if let Ok(date) = ranking.date.parse::<NaiveDate>() {
+        if let Some(assembled) = assemble_ranking(
+            &date,
+            ranking.discipline,
+            competitions,
+            pilots,
+            rankings,
+            config,
+        ) {
+            new_ranking.ranking_points = assembled.ranking_points;
+        }
+    }
+    new_ranking
+}
+
+/// Replay every competition and ranking in chronological order, scoring each
+/// competition against the preceding ranking and each ranking from the
+/// competitions recalculated before it.
+pub fn recalculate_all(
+    competitions: &[Competition],
+    rankings: &[Ranking],
+    pilots: &[Pilot],
+    config: &ScoringConfig,
+) -> (Vec<Competition>, Vec<Ranking>) {
+    let mut new_competitions: Vec<Competition> = Vec::new();
+    let mut new_rankings: Vec<Ranking> = Vec::new();
+    for event in chronological_events(competitions, rankings) {
+        match event {
+            CompetitionOrRanking::Competition(competition) => {
+                let ranking = prior_ranking(competition, &new_rankings, config);
+                new_competitions.push(
+                    recalculate_competition(competition, ranking, &new_competitions, config)
+                        .unwrap_or(competition.clone()),
+                );
+            }
+            CompetitionOrRanking::Ranking(ranking) => {
+                let ranking =
+                    recalculate_ranking(ranking, &new_competitions, pilots, &new_rankings, config);
+                new_rankings.push(ranking);
+            }
+        }
+    }
+    (new_competitions, new_rankings)
+}
+
+/// Add a competition, which may be back-dated, and recalculate only what it
+/// changes: later competitions with a changed competition in their Pn average
+/// or a changed prior ranking, and later rankings with a changed competition in
+/// their window or a changed previous ranking, in chronological order.
+/// Everything else keeps its stored values, so when those came from a replay
+/// the result is the same as `recalculate_all`. A stored competition with the
+/// same id is replaced, and what its stored version was counted in is
+/// recalculated as well so it can be moved to another date or discipline.
+pub fn recalculate_with_competition(
+    competition: &Competition,
+    competitions: &[Competition],
+    rankings: &[Ranking],
+    pilots: &[Pilot],
+    config: &ScoringConfig,
+) -> (Vec<Competition>, Vec<Ranking>) {
+    let mut changed_competitions: Vec<Competition> = competitions
+        .iter()
+        .filter(|c| c.id == competition.id)
+        .cloned()
+        .collect();
+    let competitions: Vec<Competition> = competitions
+        .iter()
+        .filter(|c| c.id != competition.id)
+        .cloned()
+        .chain([competition.clone()])
+        .collect();
+    let mut new_competitions: Vec<Competition> = Vec::new();
+    let mut new_rankings: Vec<Ranking> = Vec::new();
+    let mut changed_rankings: Vec<(String, Discipline)> = Vec::new();
+    let ranking_changed = |ranking: Option<&Ranking>, changed: &[(String, Discipline)]| {
+        ranking.is_some_and(|r| {
+            changed
+                .iter()
+                .any(|(date, discipline)| *date == r.date && *discipline == r.discipline)
+        })
+    };
+    for event in chronological_events(&competitions, rankings) {
+        match event {
+            CompetitionOrRanking::Competition(stored) => {
+                let ranking = prior_ranking(stored, &new_rankings, config);
+                let affected = stored.id == competition.id
+                    || ranking_changed(ranking, &changed_rankings)
+                    || stored.comp_date.parse::<NaiveDate>().is_ok_and(|date| {
+                        let months = config.rules_for(&date).pn_months;
+                        any_in_window(&changed_competitions, stored.discipline, &date, months)
+                    });
+                if !affected {
+                    new_competitions.push(stored.clone());
+                    continue;
+                }
+                let recalculated =
+                    recalculate_competition(stored, ranking, &new_competitions, config)
+                        .unwrap_or(stored.clone());
+                if stored.id == competition.id || competition_scores_changed(stored, &recalculated)
+                {
+                    changed_competitions.push(recalculated.clone());
+                }
+                new_competitions.push(recalculated);
+            }
+            CompetitionOrRanking::Ranking(stored) => {
+                let affected = stored.date.parse::<NaiveDate>().is_ok_and(|date| {
+                    let months = config.rules_for(&date).ranking_months;
+                    ranking_changed(
+                        previous_ranking(&date, stored.discipline, &new_rankings),
+                        &changed_rankings,
+                    ) || any_in_window(&changed_competitions, stored.discipline, &date, months)
+                });
+                if !affected {
+                    new_rankings.push(stored.clone());
+                    continue;
+                }
+                let recalculated =
+                    recalculate_ranking(stored, &new_competitions, pilots, &new_rankings, config);
+                if ranking_scores_changed(stored, &recalculated) {
+                    changed_rankings.push((stored.date.clone(), stored.discipline));
                 }
+                new_rankings.push(recalculated);
             }
         }
-        Ok(())
     }
+    (new_competitions, new_rankings)
+}
 
-    #[test]
+/// Whether any of the competitions in the discipline falls in the months
+/// before the date
+fn any_in_window(
+    competitions: &[Competition],
+    discipline: Discipline,
+    date: &NaiveDate,
+    months: u32,
+) -> bool {
+    let start = date
+        .checked_sub_months(Months::new(months))
+        .unwrap_or(NaiveDate::MIN);
+    competitions
+        .iter()
+        .any(|c| c.discipline == discipline && in_ranking_window(c, &start, date))
+}
+
+fn is_different(a: f64, b: f64) -> bool {
+    (a - b).abs() > 0.00000001
+}
+
+/// Whether recalculating changed what a competition adds to later scoring,
+/// other fields of a stored competition can differ without it having changed
+fn competition_scores_changed(stored: &Competition, recalculated: &Competition) -> bool {
+    let pq = |competition: &Competition| competition.pq.as_f64().unwrap_or_default();
+    is_different(pq(stored), pq(recalculated))
+        || is_different(stored.pn, recalculated.pn)
+        || is_different(stored.ta, recalculated.ta)
+        || is_different(stored.comp_value, recalculated.comp_value)
+        || stored.placings.len() != recalculated.placings.len()
+        || recalculated.placings.iter().any(|placing| {
+            stored
+                .placings
+                .iter()
+                .find(|p| p.pilot.pin == placing.pilot.pin && p.place == placing.place)
+                .map_or(true, |p| is_different(p.points, placing.points))
+        })
+}
+
+/// Whether recalculating changed a ranking's points or positions
+fn ranking_scores_changed(stored: &Ranking, recalculated: &Ranking) -> bool {
+    stored.ranking_points.len() != recalculated.ranking_points.len()
+        || recalculated.ranking_points.iter().any(|point| {
+            stored
+                .ranking_points
+                .iter()
+                .find(|p| p.pilot_pin == point.pilot_pin)
+                .map_or(true, |p| {
+                    p.position != point.position || is_different(p.total_points, point.total_points)
+                })
+        })
+}
+
+/// Compare recalculated competitions and rankings with the stored ones
+pub fn summarise_recalculation(
+    stored_competitions: &[Competition],
+    stored_rankings: &[Ranking],
+    competitions: &[Competition],
+    rankings: &[Ranking],
+) -> RecalculationSummary {
+    let competition_changes = competitions
+        .iter()
+        .filter_map(|competition| {
+            let stored = stored_competitions
+                .iter()
+                .find(|c| c.id == competition.id)?;
+            let placings_changed = competition
+                .placings
+                .iter()
+                .filter(|placing| {
+                    stored
+                        .placings
+                        .iter()
+                        .find(|p| p.pilot.pin == placing.pilot.pin && p.place == placing.place)
+                        .map_or(true, |p| is_different(p.points, placing.points))
+                })
+                .count();
+            let previous_pq = stored.pq.as_f64().unwrap_or_default();
+            let pq = competition.pq.as_f64().unwrap_or_default();
+            if placings_changed == 0
+                && !is_different(stored.comp_value, competition.comp_value)
+                && !is_different(previous_pq, pq)
+                && !is_different(stored.pn, competition.pn)
+            {
+                return None;
+            }
+            Some(CompetitionChange {
+                id: competition.id.clone(),
+                name: competition.name.clone(),
+                comp_date: competition.comp_date.clone(),
+                previous_comp_value: stored.comp_value,
+                comp_value: competition.comp_value,
+                previous_pq,
+                pq,
+                previous_pn: stored.pn,
+                pn: competition.pn,
+                placings_changed,
+            })
+        })
+        .collect();
+    let ranking_changes = rankings
+        .iter()
+        .filter_map(|ranking| {
+            let stored = stored_rankings
+                .iter()
+                .find(|r| r.date == ranking.date && r.discipline == ranking.discipline)?;
+            let mut pins: Vec<&String> = ranking
+                .ranking_points
+                .iter()
+                .chain(stored.ranking_points.iter())
+                .map(|p| &p.pilot_pin)
+                .collect();
+            pins.sort();
+            pins.dedup();
+            let differences: Vec<f64> = pins
+                .iter()
+                .map(|pin| {
+                    let total = |points: &Vec<RankingPoint>| {
+                        points
+                            .iter()
+                            .find(|p| &&p.pilot_pin == pin)
+                            .map_or(0.0, |p| p.total_points)
+                    };
+                    (total(&ranking.ranking_points) - total(&stored.ranking_points)).abs()
+                })
+                .filter(|difference| is_different(*difference, 0.0))
+                .collect();
+            if differences.is_empty() {
+                return None;
+            }
+            Some(RankingChange {
+                date: ranking.date.clone(),
+                discipline: ranking.discipline,
+                pilots_changed: differences.len(),
+                largest_difference: differences.iter().cloned().fold(0.0, f64::max),
+            })
+        })
+        .collect();
+    RecalculationSummary {
+        applied: false,
+        competitions_checked: competitions.len(),
+        rankings_checked: rankings.len(),
+        competitions: competition_changes,
+        rankings: ranking_changes,
+    }
+}
 
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::data::{data_access, scoring_config::PilotPointsMode};
+    use frontend::prs_data_types::PlacingStatus;
+
+    #[test]
     fn recalculate_should_get_good() -> Result<()> {
         let root = data_access::load_data()?;
         let pn = calculate_rankings(
             &root.rankings[0].date.parse::<NaiveDate>().unwrap(),
+            Discipline::ParaglidingXc,
             &root.competitions,
+            &ScoringConfig::default(),
         );
-        println!("{} {}", &root.rankings[0].id, &root.rankings[0].date);
         if let Some(mut pn) = pn {
             pn.sort_by(|a, b| a.pilot_pin.cmp(&b.pilot_pin));
             for point in pn {
                 for existing_ranking in &root.rankings[0].ranking_points {
                     if existing_ranking.pilot_pin == point.pilot_pin {
-                        println!("{} {}", point.pilot_pin, point.total_points);
-                        assert_eq!(
-                            (point.total_points - existing_ranking.total_points).abs() < 0.00000001,
-                            true
-                        );
+                        assert!(!is_different(
+                            point.total_points,
+                            existing_ranking.total_points
+                        ));
                     }
                 }
             }
@@ -292,53 +1127,374 @@ mod tests {
 
     #[test]
     fn competitions_should_decay() {
-        assert_eq!(competition_decay(10.0), 0.99889299013837107);
-        assert_eq!(competition_decay(549.0), 0.49683787436410787);
-        assert_eq!(competition_decay(1086.0), 0.0011070098616289667);
+        assert_eq!(
+            competition_decay(10.0, &ScoringRules::default()),
+            0.99889299013837107
+        );
+        assert_eq!(
+            competition_decay(549.0, &ScoringRules::default()),
+            0.49683787436410787
+        );
+        assert_eq!(
+            competition_decay(1086.0, &ScoringRules::default()),
+            0.0011070098616289667
+        );
     }
 
     #[test]
     fn pq_no_ranking() {
         let (_, _, competitions) = get_test_data();
         for comp in &competitions {
-            let pq = pilot_quality(None, &comp.placings);
+            let pq = pilot_quality(None, &comp.placings, &ScoringRules::default());
             assert_eq!(pq, 1.0);
         }
     }
 
     #[test]
     fn pq_with_ranking() {
-        let (mut rankings, _, competitions) = get_test_data();
+        let (mut rankings, pilots, competitions) = get_test_data();
         // Now create a ranking and check again
         let ranking = Ranking {
             id: "2013-09-10".to_string(),
             date: "2013-09-10".to_string(),
             ranking_points: calculate_rankings(
                 &"2013-09-10".to_string().parse::<NaiveDate>().unwrap(),
+                Discipline::ParaglidingXc,
                 &competitions,
+                &ScoringConfig::default(),
             )
             .unwrap(),
+            ..Default::default()
         };
 
         rankings.push(ranking);
-        let result = recalculate_all(rankings, competitions);
-        result.iter().for_each(|c| match c {
-            CompetitionOrRanking::Competition(c) => {
-                println!("{} {} {}", c.name, c.comp_date, c.pq);
-                if c.comp_date == "2013-09-09" {
-                    assert_eq!(1.0, c.pq);
-                } else if c.comp_date == "2014-10-05" {
-                    assert_eq!(1.0, c.pq);
-                } else if c.comp_date == "2015-08-03" {
-                    assert_eq!(0.7464150943396228, c.pq);
-                }
+        let (competitions, _) =
+            recalculate_all(&competitions, &rankings, &pilots, &ScoringConfig::default());
+        competitions.iter().for_each(|c| {
+            if c.comp_date == "2013-09-09" {
+                assert_eq!(1.0, c.pq);
+            } else if c.comp_date == "2014-10-05" {
+                assert_eq!(1.0, c.pq);
+            } else if c.comp_date == "2015-08-03" {
+                assert_eq!(0.7464150943396228, c.pq);
             }
-            CompetitionOrRanking::Ranking(r) => {
-                for p in &r.ranking_points {
-                    println!("{} {} {}", p.pilot_first_name, p.pilot_pin, p.total_points);
+        });
+    }
+
+    #[test]
+    fn recalculate_all_should_match_incremental_scoring() {
+        let (rankings, pilots, competitions) = get_test_data();
+        let (new_competitions, new_rankings) =
+            recalculate_all(&competitions, &rankings, &pilots, &ScoringConfig::default());
+        let summary =
+            summarise_recalculation(&competitions, &rankings, &new_competitions, &new_rankings);
+        assert_eq!(summary.competitions_checked, 3);
+        assert_eq!(summary.rankings_checked, 1);
+        assert_eq!(summary.competitions, vec![]);
+        assert_eq!(summary.rankings, vec![]);
+    }
+
+    #[test]
+    fn back_dated_competition_should_match_full_replay() -> Result<()> {
+        let data = data_access::load_data()?;
+        let config = ScoringConfig::default();
+        let (competitions, rankings) =
+            recalculate_all(&data.competitions, &data.rankings, &data.pilots, &config);
+        let mut by_date = data.competitions.clone();
+        by_date.sort_by(|a, b| a.comp_date.cmp(&b.comp_date));
+        let back_dated = &by_date[by_date.len() / 2];
+        let others: Vec<Competition> = data
+            .competitions
+            .iter()
+            .filter(|c| c.id != back_dated.id)
+            .cloned()
+            .collect();
+        let (stored_competitions, stored_rankings) =
+            recalculate_all(&others, &data.rankings, &data.pilots, &config);
+
+        let (new_competitions, new_rankings) = recalculate_with_competition(
+            back_dated,
+            &stored_competitions,
+            &stored_rankings,
+            &data.pilots,
+            &config,
+        );
+        let summary =
+            summarise_recalculation(&competitions, &rankings, &new_competitions, &new_rankings);
+        assert_eq!(summary.competitions_checked, competitions.len());
+        assert_eq!(summary.rankings_checked, rankings.len());
+        assert_eq!(summary.competitions, vec![]);
+        assert_eq!(summary.rankings, vec![]);
+        // Adding it does change later rankings
+        let changed = summarise_recalculation(
+            &stored_competitions,
+            &stored_rankings,
+            &new_competitions,
+            &new_rankings,
+        );
+        assert!(!changed.rankings.is_empty());
+        assert!(changed
+            .rankings
+            .iter()
+            .all(|r| r.date.as_str() > back_dated.comp_date.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn moved_competition_should_match_full_replay() -> Result<()> {
+        let data = data_access::load_data()?;
+        let config = ScoringConfig::default();
+        let (stored_competitions, stored_rankings) =
+            recalculate_all(&data.competitions, &data.rankings, &data.pilots, &config);
+        let mut by_date = stored_competitions.clone();
+        by_date.sort_by(|a, b| a.comp_date.cmp(&b.comp_date));
+        let moved = Competition {
+            comp_date: by_date[by_date.len() - 1].comp_date.clone(),
+            ..by_date[by_date.len() / 2].clone()
+        };
+        let moved_competitions: Vec<Competition> = data
+            .competitions
+            .iter()
+            .map(|c| match c.id == moved.id {
+                true => moved.clone(),
+                false => c.clone(),
+            })
+            .collect();
+        let (competitions, rankings) =
+            recalculate_all(&moved_competitions, &data.rankings, &data.pilots, &config);
+
+        let (new_competitions, new_rankings) = recalculate_with_competition(
+            &moved,
+            &stored_competitions,
+            &stored_rankings,
+            &data.pilots,
+            &config,
+        );
+        let summary =
+            summarise_recalculation(&competitions, &rankings, &new_competitions, &new_rankings);
+        assert_eq!(summary.competitions, vec![]);
+        assert_eq!(summary.rankings, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn stored_values_outside_scoring_should_not_count_as_changes() {
+        let stored = Competition {
+            pq: json!(1),
+            ..competition_with_places(&[1, 2, 3])
+        };
+        let recalculated = Competition {
+            name: "Renamed".to_string(),
+            pq: json!(1.0),
+            ..stored.clone()
+        };
+        assert!(!competition_scores_changed(&stored, &recalculated));
+        let mut rescored = recalculated.clone();
+        rescored.placings[0].points += 1.0;
+        assert!(competition_scores_changed(&stored, &rescored));
+
+        let point = |pin: &str, position: usize| RankingPoint {
+            pilot_pin: pin.to_string(),
+            total_points: 100.0,
+            position,
+            ..Default::default()
+        };
+        let stored = Ranking {
+            ranking_points: vec![point("1", 1), point("2", 1)],
+            ..Default::default()
+        };
+        let reordered = Ranking {
+            ranking_points: vec![
+                RankingPoint {
+                    movement: Some(1),
+                    ..point("2", 1)
+                },
+                point("1", 1),
+            ],
+            category: Some("Women".to_string()),
+            ..stored.clone()
+        };
+        assert!(!ranking_scores_changed(&stored, &reordered));
+        let repositioned = Ranking {
+            ranking_points: vec![point("1", 1), point("2", 2)],
+            ..stored.clone()
+        };
+        assert!(ranking_scores_changed(&stored, &repositioned));
+    }
+
+    #[test]
+    fn recalculate_all_should_report_changes() {
+        let (rankings, pilots, competitions) = get_test_data();
+        let mut stored_competitions = competitions.clone();
+        stored_competitions[1].comp_value = 0.0;
+        let (new_competitions, new_rankings) = recalculate_all(
+            &stored_competitions,
+            &rankings,
+            &pilots,
+            &ScoringConfig::default(),
+        );
+        let summary = summarise_recalculation(
+            &stored_competitions,
+            &rankings,
+            &new_competitions,
+            &new_rankings,
+        );
+        assert_eq!(summary.competitions.len(), 1);
+        assert_eq!(summary.competitions[0].id, "2014-10-05-Wanaka");
+        assert_eq!(
+            summary.competitions[0].comp_value,
+            competitions[1].comp_value
+        );
+    }
+
+    #[test]
+    fn explained_points_should_match_rankings() -> Result<()> {
+        let root = data_access::load_data()?;
+        let config = ScoringConfig::default();
+        let date = root.rankings[0].date.parse::<NaiveDate>()?;
+        for point in calculate_rankings(
+            &date,
+            Discipline::ParaglidingXc,
+            &root.competitions,
+            &config,
+        )
+        .unwrap()
+        {
+            let explanation = explain_points(
+                &point.pilot_pin,
+                &date,
+                Discipline::ParaglidingXc,
+                &root.competitions,
+                &config,
+            )
+            .unwrap();
+            assert!(!is_different(explanation.total_points, point.total_points));
+            for result in &explanation.results {
+                if !result.overseas {
+                    assert!(!is_different(
+                        result.pp * result.pq * result.pn * result.ta * 100.0 * result.td,
+                        result.decayed_points
+                    ));
                 }
             }
-        });
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn explained_points_should_use_stored_task_quality() {
+        let older = ScoringConfig {
+            versions: vec![ScoringRules {
+                task_quality: vec![0.0, 0.5],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let competition = recalculate_competition(
+            &Competition {
+                id: "older".to_string(),
+                comp_date: "2022-01-01".to_string(),
+                num_tasks: 3,
+                ..competition_with_places(&[1, 2, 3])
+            },
+            None,
+            &vec![],
+            &older,
+        )
+        .unwrap();
+        let explanation = explain_points(
+            &competition.placings[0].pilot.pin,
+            &NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &[competition],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let result = &explanation.results[0];
+        assert_eq!(result.ta, 0.5);
+        assert!(!is_different(
+            result.pp * result.pq * result.pn * result.ta * 100.0 * result.td,
+            result.decayed_points
+        ));
+    }
+
+    #[test]
+    fn explained_points_should_show_overseas_cap() {
+        let overseas = |id: &str, fai_points: f64| Competition {
+            id: id.to_string(),
+            name: id.to_string(),
+            comp_date: "2022-01-01".to_string(),
+            kind: CompetitionKind::OverseasFai,
+            placings: vec![Placing {
+                pilot: CompetitionPilot {
+                    pin: "1001".to_string(),
+                    ..Default::default()
+                },
+                fai_points,
+                points: fai_points,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let competitions = vec![
+            overseas("a", 30.0),
+            overseas("b", 10.0),
+            overseas("c", 20.0),
+        ];
+        let explanation = explain_points(
+            "1001",
+            &NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &competitions,
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let statuses: Vec<(&str, ResultStatus)> = explanation
+            .results
+            .iter()
+            .map(|r| (r.comp_id.as_str(), r.status.clone()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("a", ResultStatus::Counted),
+                ("c", ResultStatus::Counted),
+                ("b", ResultStatus::OverseasCap)
+            ]
+        );
+    }
+
+    #[test]
+    fn rankings_should_use_rules_in_force() {
+        let (_, _, competitions) = get_test_data();
+        let config = ScoringConfig {
+            versions: vec![
+                ScoringRules::default(),
+                ScoringRules {
+                    version: "single-result".to_string(),
+                    effective_from: "2016-01-01".to_string(),
+                    best_results: 1,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let total = |date: &str, config: &ScoringConfig| -> f64 {
+            calculate_rankings(
+                &date.parse::<NaiveDate>().unwrap(),
+                Discipline::ParaglidingXc,
+                &competitions,
+                config,
+            )
+            .unwrap()
+            .iter()
+            .find(|r| r.pilot_pin == "1001")
+            .map(|r| r.total_points)
+            .unwrap()
+        };
+        let default = ScoringConfig::default();
+        assert_eq!(total("2015-12-01", &config), total("2015-12-01", &default));
+        assert!(total("2016-02-01", &config) < total("2016-02-01", &default));
     }
 
     #[test]
@@ -346,15 +1502,15 @@ mod tests {
         let (_, _, competitions) = get_test_data();
         assert_eq!(
             1.0,
-            participant_number(&competitions[0], &competitions).unwrap()
+            participant_number(&competitions[0], &competitions, &ScoringRules::default()).unwrap()
         );
         assert_eq!(
             0.7669649888473704,
-            participant_number(&competitions[1], &competitions).unwrap()
+            participant_number(&competitions[1], &competitions, &ScoringRules::default()).unwrap()
         );
         assert_eq!(
             0.7559289460184544,
-            participant_number(&competitions[2], &competitions).unwrap()
+            participant_number(&competitions[2], &competitions, &ScoringRules::default()).unwrap()
         );
     }
 
@@ -429,78 +1585,500 @@ mod tests {
         assert_eq!(find_pilot_placing(&pilots[4], waikato_comp.unwrap()), 0.25);
     }
 
-    #[derive(Clone)]
-    enum CompetitionOrRanking {
-        Ranking(Ranking),
-        Competition(Competition),
+    /// A five task competition with a pilot on each of the places, pins from 1001
+    fn competition_with_places(places: &[i64]) -> Competition {
+        Competition {
+            id: "2022-01-10-Wanaka".to_string(),
+            comp_date: "2022-01-10".to_string(),
+            num_tasks: 5,
+            placings: places
+                .iter()
+                .enumerate()
+                .map(|(i, place)| Placing {
+                    pilot: CompetitionPilot {
+                        pin: format!("{}", 1001 + i),
+                        ..Default::default()
+                    },
+                    place: *place,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tied_placings_should_share_averaged_pplacing() {
+        let competition = recalculate_competition(
+            &competition_with_places(&[1, 2, 2, 4]),
+            None,
+            &vec![],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let pplacings: Vec<f64> = competition.placings.iter().map(|p| p.pplacing).collect();
+        assert_eq!(pplacings, vec![1.0, 0.625, 0.625, 0.25]);
+        assert_eq!(
+            competition.placings[1].points,
+            competition.placings[2].points
+        );
+
+        let untied = recalculate_competition(
+            &competition_with_places(&[1, 2, 3, 4]),
+            None,
+            &vec![],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let total = |c: &Competition| c.placings.iter().map(|p| p.pplacing).sum::<f64>();
+        assert_eq!(total(&competition), total(&untied));
+    }
+
+    #[test]
+    fn exchange_rate_should_average_linked_competitions() {
+        let linked = |id: &str, comp_date: &str, points: f64, fai_points: f64| {
+            let mut competition = competition_with_places(&[1, 2]);
+            competition.id = id.to_string();
+            competition.comp_date = comp_date.to_string();
+            competition.civl_id = Some(id.to_string());
+            for placing in competition.placings.iter_mut() {
+                placing.points = points;
+                placing.fai_points = fai_points;
+            }
+            competition
+        };
+        let mut unlinked = linked("unlinked", "2022-06-01", 100.0, 10.0);
+        unlinked.civl_id = None;
+        let competitions = vec![
+            linked("old", "2019-06-01", 100.0, 10.0),
+            linked("a", "2021-06-01", 60.0, 20.0),
+            linked("b", "2022-06-01", 40.0, 40.0),
+            unlinked,
+        ];
+        let config = ScoringConfig::default();
+        let rate = exchange_rate(
+            &NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &competitions,
+            &config,
+        );
+        assert_eq!(rate.competitions.len(), 2);
+        assert_eq!(rate.nzprs_average, 50.0);
+        assert_eq!(rate.wprs_average, 30.0);
+        assert_eq!(rate.exchange_rate, Some(50.0 / 30.0));
+
+        let longer = ScoringConfig {
+            versions: vec![ScoringRules {
+                exchange_rate_months: 48,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let rate = exchange_rate(
+            &NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &competitions,
+            &longer,
+        );
+        assert_eq!(rate.competitions.len(), 3);
+
+        let none = exchange_rate(
+            &NaiveDate::from_ymd_opt(2010, 1, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &competitions,
+            &config,
+        );
+        assert_eq!(none.exchange_rate, None);
     }
 
-    fn recalculate_all(
-        rankings: Vec<Ranking>,
-        competitions: Vec<Competition>,
-    ) -> Vec<CompetitionOrRanking> {
-        // Put all comps and rankings into the events tree map so that they
-        // can be iterated in chronological order
-        let mut joined: Vec<(String, CompetitionOrRanking)> = rankings
+    #[test]
+    fn league_should_be_scored_as_local() {
+        let league = Competition {
+            kind: CompetitionKind::OverseasLeague,
+            exchange_rate: 5.0,
+            ..competition_with_places(&[3, 10, 10, 25, 40, 41])
+        };
+        let scored =
+            recalculate_competition(&league, None, &vec![], &ScoringConfig::default()).unwrap();
+        let places: Vec<i64> = scored.placings.iter().map(|p| p.place).collect();
+        assert_eq!(places, vec![1, 2, 2, 4, 5, 6]);
+
+        let local = recalculate_competition(
+            &competition_with_places(&[1, 2, 2, 4, 5, 6]),
+            None,
+            &vec![],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let points = |c: &Competition| c.placings.iter().map(|p| p.points).collect::<Vec<f64>>();
+        assert_eq!(points(&scored), points(&local));
+
+        let rankings = calculate_rankings(
+            &NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &vec![scored],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        assert!(rankings.iter().all(|r| !r.results[0].overseas));
+    }
+
+    #[test]
+    fn disciplines_should_be_scored_separately() {
+        let paragliding = Competition {
+            id: "2021-06-01-Bright".to_string(),
+            comp_date: "2021-06-01".to_string(),
+            ..competition_with_places(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10])
+        };
+        let hang_gliding = Competition {
+            discipline: Discipline::HangGlidingXc,
+            ..competition_with_places(&[1, 2])
+        };
+        let config = ScoringConfig::default();
+        let alone = recalculate_competition(&hang_gliding, None, &vec![], &config).unwrap();
+        let with_paragliding =
+            recalculate_competition(&hang_gliding, None, &vec![paragliding.clone()], &config)
+                .unwrap();
+        assert_eq!(alone.pn, with_paragliding.pn);
+
+        let ranking = Ranking {
+            date: "2022-01-01".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            prior_ranking(&hang_gliding, &[ranking.clone()], &config),
+            None
+        );
+        assert_eq!(
+            prior_ranking(&paragliding, &[ranking.clone()], &config),
+            None
+        );
+        assert_eq!(
+            prior_ranking(&competition_with_places(&[1]), &[ranking.clone()], &config),
+            Some(&ranking)
+        );
+
+        let competitions = vec![
+            recalculate_competition(&paragliding, None, &vec![], &config).unwrap(),
+            with_paragliding,
+        ];
+        let date = NaiveDate::from_ymd_opt(2022, 2, 1).unwrap();
+        let paragliding_ranking =
+            calculate_rankings(&date, Discipline::ParaglidingXc, &competitions, &config).unwrap();
+        let hang_gliding_ranking =
+            calculate_rankings(&date, Discipline::HangGlidingXc, &competitions, &config).unwrap();
+        assert_eq!(paragliding_ranking.len(), 10);
+        assert_eq!(hang_gliding_ranking.len(), 2);
+        assert!(hang_gliding_ranking
             .iter()
-            .map(|r| (r.date.clone(), CompetitionOrRanking::Ranking(r.clone())))
-            .collect::<Vec<(String, CompetitionOrRanking)>>();
-        let mut competition_map = competitions
+            .all(|r| r.results.iter().all(|c| c.comp_id == hang_gliding.id)));
+    }
+
+    #[test]
+    fn category_ranking_should_reposition_pilots() {
+        let mut competition = competition_with_places(&[1, 2, 3, 4]);
+        competition.placings[1].pilot.gender = "FEMALE".to_string();
+        competition.placings[3].pilot.gender = "female".to_string();
+        let scored =
+            recalculate_competition(&competition, None, &vec![], &ScoringConfig::default())
+                .unwrap();
+        let ranking = Ranking {
+            date: "2022-02-01".to_string(),
+            ranking_points: calculate_rankings(
+                &NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+                Discipline::ParaglidingXc,
+                &vec![scored],
+                &ScoringConfig::default(),
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ranking.ranking_points[1].pilot_gender,
+            Some("FEMALE".to_string())
+        );
+        let pilots = vec![
+            Pilot {
+                pin: "1001".to_string(),
+                birth_year: Some(1999),
+                ..Default::default()
+            },
+            Pilot {
+                pin: "1003".to_string(),
+                birth_year: Some(1996),
+                ..Default::default()
+            },
+        ];
+        let config = ScoringConfig::default();
+
+        let women =
+            category_ranking(&ranking, config.category("women").unwrap(), &pilots, None).unwrap();
+        let pins_and_positions = |r: &Ranking| {
+            r.ranking_points
+                .iter()
+                .map(|p| (p.pilot_pin.clone(), p.position))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(women.category, Some("women".to_string()));
+        assert_eq!(
+            pins_and_positions(&women),
+            vec![("1002".to_string(), 1), ("1004".to_string(), 2)]
+        );
+        // Born 1996 turns 26 in 2022 so is too old, 1004 has no birth year
+        let juniors =
+            category_ranking(&ranking, config.category("juniors").unwrap(), &pilots, None).unwrap();
+        assert_eq!(pins_and_positions(&juniors), vec![("1001".to_string(), 1)]);
+    }
+
+    #[test]
+    fn assembled_ranking_should_have_pilot_details_and_movement() {
+        let mut competition = competition_with_places(&[1, 2, 3]);
+        competition.placings[1].pilot.first_name = "Placing".to_string();
+        competition.placings[1].pilot.last_name = "Name".to_string();
+        let scored =
+            recalculate_competition(&competition, None, &vec![], &ScoringConfig::default())
+                .unwrap();
+        let pilots = vec![Pilot {
+            pin: "1001".to_string(),
+            first_name: "Registry".to_string(),
+            last_name: "Name".to_string(),
+            gender: "FEMALE".to_string(),
+            ..Default::default()
+        }];
+        // Stored before positions were kept
+        let previous = Ranking {
+            date: "2022-01-15".to_string(),
+            ranking_points: ["1003", "1001"]
+                .iter()
+                .enumerate()
+                .map(|(i, pin)| RankingPoint {
+                    pilot_pin: pin.to_string(),
+                    total_points: 10.0 - i as f64,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let ranking = assemble_ranking(
+            &NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &vec![scored],
+            &pilots,
+            &[previous],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let details: Vec<(&str, &str, usize, Option<usize>, Option<i64>)> = ranking
+            .ranking_points
             .iter()
-            .map(|r| {
+            .map(|p| {
                 (
-                    r.comp_date.clone(),
-                    CompetitionOrRanking::Competition(r.clone()),
+                    p.pilot_first_name.as_str(),
+                    p.pilot_last_name.as_str(),
+                    p.position,
+                    p.previous_position,
+                    p.movement,
                 )
             })
-            .collect::<Vec<(String, CompetitionOrRanking)>>();
-        joined.append(competition_map.as_mut());
-        joined.sort_by(|a, b| a.0.cmp(&b.0));
-        let mut previous_ranking: Option<Ranking> = None;
-        let mut competition_new = joined
-            .iter()
-            .map(|f| f.clone())
-            .collect::<HashMap<String, CompetitionOrRanking>>();
-        for o in joined {
-            match o.1 {
-                CompetitionOrRanking::Competition(comp) => {
-                    let newcomp = recalculate_competition(
-                        &comp,
-                        previous_ranking.as_ref(),
-                        &competition_new
-                            .clone()
-                            .into_values()
-                            .map(|c| match c {
-                                CompetitionOrRanking::Competition(comp) => Some(comp),
-                                _ => None,
-                            })
-                            .flatten()
-                            .collect(),
-                    );
-
-                    if let Some(c) = newcomp {
-                        competition_new.insert(
-                            c.comp_date.clone(),
-                            CompetitionOrRanking::Competition(c.clone()),
-                        );
-                    }
-                }
-                CompetitionOrRanking::Ranking(ranking) => {
-                    let ranking_points = calculate_rankings(
-                        &ranking.date.parse::<NaiveDate>().unwrap(),
-                        &competitions,
-                    );
-                    if let Some(points) = ranking_points {
-                        let mut r = ranking.clone();
-                        r.ranking_points = points;
-                        competition_new
-                            .insert(r.date.clone(), CompetitionOrRanking::Ranking(r.clone()));
-                        previous_ranking = Some(r.clone());
-                    }
-                }
-            }
+            .collect();
+        assert_eq!(ranking.date, "2022-02-01");
+        assert_eq!(
+            details,
+            vec![
+                ("Registry", "Name", 1, Some(2), Some(1)),
+                ("Placing", "Name", 2, None, None),
+                ("1003", "1003", 3, Some(1), Some(-2)),
+            ]
+        );
+        assert_eq!(
+            ranking.ranking_points[0].pilot_gender,
+            Some("FEMALE".to_string())
+        );
+    }
+
+    #[test]
+    fn pilot_history_should_prefer_stored_rankings() {
+        let scored = recalculate_competition(
+            &competition_with_places(&[1, 2]),
+            None,
+            &vec![],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let stored = Ranking {
+            date: "2022-03-01".to_string(),
+            ranking_points: vec![RankingPoint {
+                pilot_pin: "1002".to_string(),
+                total_points: 99.0,
+                position: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let dates =
+            ["2022-01-01", "2022-02-01", "2022-03-01"].map(|d| d.parse::<NaiveDate>().unwrap());
+        let history = pilot_history(
+            "1002",
+            Discipline::ParaglidingXc,
+            &dates,
+            &vec![scored],
+            &[stored],
+            &ScoringConfig::default(),
+        );
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].position, None);
+        assert_eq!(history[0].total_points, 0.0);
+        assert_eq!(history[1].position, Some(2));
+        assert_eq!(history[1].counted_results.len(), 1);
+        assert_eq!(
+            history[1].total_points,
+            history[1].counted_results[0].points
+        );
+        assert_eq!(history[2].position, Some(1));
+        assert_eq!(history[2].total_points, 99.0);
+    }
+
+    #[test]
+    fn forecast_should_value_top_pilot_additions() {
+        let ranking = Ranking {
+            date: "2022-01-01".to_string(),
+            ranking_points: [("1001", 300.0), ("1002", 200.0), ("1003", 100.0)]
+                .iter()
+                .map(|(pin, points)| RankingPoint {
+                    pilot_pin: pin.to_string(),
+                    total_points: *points,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let request = ForecastRequest {
+            comp_date: "2022-02-10".to_string(),
+            num_tasks: 3,
+            pins: vec!["1003".to_string(), "2001".to_string()],
+            ..Default::default()
+        };
+        let config = ScoringConfig::default();
+        let rules = config.rules_for(&NaiveDate::from_ymd_opt(2022, 2, 10).unwrap());
+
+        let forecast = forecast_competition(&request, &vec![], &[ranking], &config).unwrap();
+
+        assert_eq!(
+            forecast.pq,
+            100.0 / 300.0 * (1.0 - rules.pq_min) + rules.pq_min
+        );
+        assert_eq!(forecast.pn, 1.0);
+        assert_eq!(
+            forecast.ta_max,
+            rules.task_quality(Discipline::ParaglidingXc, 3)
+        );
+        assert_eq!(
+            forecast.ta_min,
+            rules.task_quality(Discipline::ParaglidingXc, 1)
+        );
+        assert_eq!(
+            forecast.winner_points,
+            forecast.pq * forecast.pn * forecast.ta_max * 100.0
+        );
+        assert_eq!(
+            forecast
+                .additions
+                .iter()
+                .map(|a| (a.pilot_pin.as_str(), a.position))
+                .collect::<Vec<_>>(),
+            vec![("1001", 1), ("1002", 2)]
+        );
+        assert!(forecast.additions[1].value_increase > 0.0);
+    }
+
+    #[test]
+    fn score_mode_should_follow_score_share() {
+        let mut competition = competition_with_places(&[1, 2, 3]);
+        for (placing, score) in competition.placings.iter_mut().zip([1000.0, 998.0, 500.0]) {
+            placing.score = Some(score);
         }
-        competition_new.clone().into_values().collect()
+        let config = |pp_mode: PilotPointsMode| ScoringConfig {
+            versions: vec![ScoringRules {
+                pp_mode,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let pp = |pp_mode: PilotPointsMode| -> Vec<f64> {
+            recalculate_competition(&competition, None, &vec![], &config(pp_mode))
+                .unwrap()
+                .placings
+                .iter()
+                .map(|p| p.pp)
+                .collect()
+        };
+
+        let by_placing = pp(PilotPointsMode::Placing);
+        let by_score = pp(PilotPointsMode::Score);
+        let blended = pp(PilotPointsMode::Blended);
+        // Pq is 1 without a prior ranking so Pp is the factor squared
+        assert_eq!(by_placing[1], (2.0_f64 / 3.0).powf(2.0));
+        assert_eq!(by_score[0], 1.0);
+        assert_eq!(by_score[1], 0.998_f64.powf(2.0));
+        assert_eq!(by_score[2], 0.25);
+        assert_eq!(blended[1], ((2.0 / 3.0 + 0.998) / 2.0_f64).powf(2.0));
+    }
+
+    #[test]
+    fn placing_status_should_follow_policy() {
+        let mut competition = competition_with_places(&[1, 2, 3, 4, 5]);
+        competition.placings[3].status = PlacingStatus::Disqualified;
+        competition.placings[4].status = PlacingStatus::DidNotFly;
+        let history = vec![competition_with_places(&[1, 2, 3, 4])];
+        let scored = recalculate_competition(
+            &Competition {
+                comp_date: "2022-02-10".to_string(),
+                ..competition
+            },
+            None,
+            &history,
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+
+        // Four participants against an average of four
+        assert_eq!(scored.pn, 1.0);
+        let pplacings: Vec<f64> = scored.placings.iter().map(|p| p.pplacing).collect();
+        assert_eq!(pplacings, vec![1.0, 0.75, 0.5, 0.0, 0.0]);
+        assert_eq!(scored.placings[3].points, 0.0);
+
+        let rankings = calculate_rankings(
+            &NaiveDate::from_ymd_opt(2022, 3, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &vec![scored],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(rankings.len(), 3);
+        assert!(!rankings
+            .iter()
+            .any(|r| r.pilot_pin == "1004" || r.pilot_pin == "1005"));
+    }
+
+    #[test]
+    fn tied_rankings_should_share_position() {
+        let competition = recalculate_competition(
+            &competition_with_places(&[1, 2, 2, 4]),
+            None,
+            &vec![],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let rankings = calculate_rankings(
+            &NaiveDate::from_ymd_opt(2022, 2, 1).unwrap(),
+            Discipline::ParaglidingXc,
+            &vec![competition],
+            &ScoringConfig::default(),
+        )
+        .unwrap();
+        let positions: Vec<usize> = rankings.iter().map(|r| r.position).collect();
+        assert_eq!(positions, vec![1, 2, 2, 4]);
     }
 
     fn get_test_data() -> (Vec<Ranking>, Vec<Pilot>, Vec<Competition>) {
@@ -510,72 +2088,84 @@ mod tests {
                 first_name: "First".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1002".to_string(),
                 first_name: "Second".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1003".to_string(),
                 first_name: "Third".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1004".to_string(),
                 first_name: "Fourth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1005".to_string(),
                 first_name: "Fifth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1006".to_string(),
                 first_name: "Sixth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1007".to_string(),
                 first_name: "Seventh".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1008".to_string(),
                 first_name: "Eighth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1009".to_string(),
                 first_name: "Nineth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1010".to_string(),
                 first_name: "Tenth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1011".to_string(),
                 first_name: "Eleventh".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
             Pilot {
                 pin: "1012".to_string(),
                 first_name: "Twelth".to_string(),
                 last_name: "Pilot".to_string(),
                 gender: "None".to_string(),
+                ..Default::default()
             },
         ]
         .to_vec();
@@ -613,7 +2203,6 @@ mod tests {
                 name: "Auckland Regional Nov 2013".to_string(),
                 location: "Auckland".to_string(),
                 comp_date: "2013-09-09".to_string(),
-                overseas: false,
                 exchange_rate: 1.0,
                 num_tasks: 1,
                 pn: 0.0,
@@ -639,11 +2228,14 @@ mod tests {
                         pplacing: 0.0,
                         fai_points: 0.0,
                         pp: 0.0,
+                        ..Default::default()
                     })
                     .collect(),
+                ..Default::default()
             },
             None,
             &comps,
+            &ScoringConfig::default(),
         );
         if let Some(comp) = auckland {
             comps.push(comp);
@@ -654,7 +2246,6 @@ mod tests {
                 name: "Wanaka".to_string(),
                 location: "Wanaka".to_string(),
                 comp_date: "2014-10-05".to_string(),
-                overseas: false,
                 exchange_rate: 1.0,
                 num_tasks: 2,
                 pn: 0.0,
@@ -680,11 +2271,14 @@ mod tests {
                         pplacing: 0.0,
                         fai_points: 0.0,
                         pp: 0.0,
+                        ..Default::default()
                     })
                     .collect(),
+                ..Default::default()
             },
             None,
             &comps,
+            &ScoringConfig::default(),
         );
         if let Some(comp) = wanaka {
             comps.push(comp);
@@ -695,7 +2289,6 @@ mod tests {
                 name: "Waikato".to_string(),
                 location: "Waikato".to_string(),
                 comp_date: "2015-08-03".to_string(),
-                overseas: false,
                 exchange_rate: 1.0,
                 num_tasks: 6,
                 pn: 0.0,
@@ -721,11 +2314,14 @@ mod tests {
                         pplacing: 0.0,
                         fai_points: 0.0,
                         pp: 0.0,
+                        ..Default::default()
                     })
                     .collect(),
+                ..Default::default()
             },
             None,
             &comps,
+            &ScoringConfig::default(),
         );
         if let Some(comp) = waikato {
             comps.push(comp);
@@ -735,9 +2331,12 @@ mod tests {
             id: "2013-09-09".to_string(),
             ranking_points: calculate_rankings(
                 &"2013-09-09".to_string().parse::<NaiveDate>().unwrap(),
+                Discipline::ParaglidingXc,
                 &comps,
+                &ScoringConfig::default(),
             )
             .unwrap(),
+            ..Default::default()
         };
 
         ([ranking].to_vec(), pilots, comps)
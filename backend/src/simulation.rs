@@ -0,0 +1,122 @@
+use chrono::NaiveDate;
+use frontend::prs_data_types::{Competition, Ranking, RankingPoint, SimulationResult};
+
+use crate::{comparison, data::scoring_config::ScoringConfig, scoring};
+
+/// Score a draft competition against the ranking before it and work out the
+/// ranking on `ranking_date` with and without it. A draft with the id of a
+/// stored competition stands in for that competition. Nothing is saved.
+pub fn simulate_competition(
+    draft: &Competition,
+    ranking_date: &NaiveDate,
+    competitions: &[Competition],
+    rankings: &[Ranking],
+    config: &ScoringConfig,
+) -> Option<SimulationResult> {
+    let others: Vec<Competition> = competitions
+        .iter()
+        .filter(|c| c.id != draft.id)
+        .cloned()
+        .collect();
+    let scored = scoring::recalculate_competition(
+        draft,
+        scoring::prior_ranking(draft, rankings, config),
+        &others,
+        config,
+    )?;
+    let before = scoring::calculate_rankings(
+        ranking_date,
+        draft.discipline,
+        &competitions.to_vec(),
+        config,
+    )?;
+    let mut with_draft = others;
+    with_draft.push(scored.clone());
+    let after = scoring::calculate_rankings(ranking_date, draft.discipline, &with_draft, config)?;
+    let find = |points: &[RankingPoint], pin: &str| -> Option<RankingPoint> {
+        points.iter().find(|p| p.pilot_pin == pin).cloned()
+    };
+    let rules = config.rules_for(ranking_date);
+    let changes = after
+        .iter()
+        .map(|point| {
+            let before_point = find(&before, &point.pilot_pin);
+            comparison::pilot_movement(before_point.as_ref(), Some(point), rules, rules)
+        })
+        .chain(
+            before
+                .iter()
+                .filter(|point| find(&after, &point.pilot_pin).is_none())
+                .map(|point| comparison::pilot_movement(Some(point), None, rules, rules)),
+        )
+        .filter(|m| m.from_position != m.to_position || m.from_points != m.to_points)
+        .collect();
+    Some(SimulationResult {
+        competition: scored,
+        ranking_date: ranking_date.format("%Y-%m-%d").to_string(),
+        changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frontend::prs_data_types::{CompetitionPilot, Placing};
+    use serde_json::json;
+
+    fn competition(id: &str, comp_date: &str, pins: &[&str]) -> Competition {
+        Competition {
+            id: id.to_string(),
+            comp_date: comp_date.to_string(),
+            num_tasks: 5,
+            pq: json!(1.0),
+            placings: pins
+                .iter()
+                .enumerate()
+                .map(|(i, pin)| Placing {
+                    pilot: CompetitionPilot {
+                        pin: pin.to_string(),
+                        ..Default::default()
+                    },
+                    place: i as i64 + 1,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn simulation_should_show_ranking_changes() {
+        let config = ScoringConfig::default();
+        let stored = scoring::recalculate_competition(
+            &competition("wanaka", "2022-01-10", &["1001", "1002", "1003"]),
+            None,
+            &vec![],
+            &config,
+        )
+        .unwrap();
+        let draft = competition("draft", "2022-02-10", &["1003", "1004"]);
+        let date = NaiveDate::from_ymd_opt(2022, 3, 1).unwrap();
+
+        let result = simulate_competition(&draft, &date, &[stored], &[], &config).unwrap();
+
+        assert_eq!(result.ranking_date, "2022-03-01");
+        assert!(result.competition.comp_value > 0.0);
+        let winner = result
+            .changes
+            .iter()
+            .find(|m| m.pilot_pin == "1003")
+            .unwrap();
+        assert_eq!(winner.from_position, Some(3));
+        assert_eq!(winner.to_position, Some(1));
+        assert!(winner.points_from_new_competitions > 0.0);
+        assert!(winner.to_points > winner.from_points);
+        let newcomer = result
+            .changes
+            .iter()
+            .find(|m| m.pilot_pin == "1004")
+            .unwrap();
+        assert_eq!(newcomer.from_position, None);
+    }
+}
@@ -155,7 +155,7 @@ pub fn competition_list(props: &CompetitionDetailProps) -> Html {
                     competition.placings.iter().map(|placing|
                         html!{
                         <tr>
-                            <td>{&placing.place}</td>
+                            <td>{if placing.status.earns_points() { placing.place.to_string() } else { placing.status.code().to_string() }}</td>
                             <td><Link<AppRoute> to={AppRoute::PilotDetail {pin: placing.pilot.pin.clone()}}>
                                 {format!("{} {}", &placing.pilot.first_name, &placing.pilot.last_name)}
                             </Link<AppRoute>></td>
@@ -1,10 +1,20 @@
 use std::rc::Rc;
 
-use crate::{data::prs_data_types::Competition, data::*, routes::AppRoute};
+use crate::{
+    data::csv::read_csv,
+    data::prs_data_types::{
+        Competition, CompetitionKind, CsvField, CsvMapping, CsvUpload, Discipline,
+        ImportedCompetition, PilotMatch, PilotMatchKind, PlacingStatus, TaskQuality,
+        TaskQualityMode, LEAGUE_MIN_PILOTS,
+    },
+    data::*,
+    routes::AppRoute,
+};
 use validator::Validate;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 use yew::InputEvent;
+use yew_hooks::{use_async, use_local_storage};
 use yew_router::prelude::Link;
 use yewdux::prelude::*;
 use yewdux_input::InputDispatch;
@@ -61,18 +71,128 @@ fn validation_message(field: &str, state: &Rc<Competition>) -> Option<String> {
     }
 }
 
-async fn get_highcloud_comp(url_string: &String) -> Result<Competition, MultiError> {
-    get_data(format!("/competition/fromhc/{}", url_string)).await
+async fn get_highcloud_comp(url_string: &String) -> Result<ImportedCompetition, MultiError> {
+    get_data(format!("/import/highcloud/{}", url_string)).await
 }
 
-async fn get_fai_comp(url_string: &String) -> Result<Competition, MultiError> {
-    get_data(format!("/competition/fromfai/{}", url_string)).await
+async fn get_fai_comp(url_string: &String) -> Result<ImportedCompetition, MultiError> {
+    get_data(format!("/import/civl/{}", url_string)).await
+}
+
+async fn upload_fsdb(contents: String, token: String) -> Result<ImportedCompetition, MultiError> {
+    post_authorized_data("/import/fsdb".to_string(), contents, token).await
+}
+
+async fn upload_csv(upload: &CsvUpload, token: String) -> Result<ImportedCompetition, MultiError> {
+    let body = serde_json::to_string(upload).map_err(|_| MultiError::RequestError)?;
+    post_authorized_data("/import/csv".to_string(), body, token).await
+}
+
+/// Fill the form from an import, keeping its task scores and unread rows to
+/// show alongside
+fn apply_import(
+    state: &mut Competition,
+    report: &UseStateHandle<ImportedCompetition>,
+    imported: ImportedCompetition,
+) {
+    let comp = imported.competition;
+    report.set(ImportedCompetition {
+        competition: Competition::default(),
+        ..imported
+    });
+    state.name = comp.name;
+    state.location = comp.location;
+    state.comp_date = comp.comp_date;
+    state.num_tasks = comp.num_tasks;
+    state.kind = comp.kind;
+    state.exchange_rate = comp.exchange_rate;
+    state.civl_id = comp.civl_id;
+    state.task_validity = comp.task_validity;
+    state.total_validity = comp.total_validity;
+    state.placings = comp.placings;
+}
+
+async fn get_task_quality(
+    comp_date: String,
+    discipline: Discipline,
+    num_tasks: i64,
+    total_validity: Option<f64>,
+) -> Result<TaskQuality, MultiError> {
+    let validity = total_validity
+        .map(|validity| format!("&totalValidity={}", validity))
+        .unwrap_or_default();
+    get_data(format!(
+        "/taskquality/{}?discipline={}&numTasks={}{}",
+        comp_date, discipline, num_tasks, validity
+    ))
+    .await
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TaskQualityProps {
+    pub comp_date: String,
+    pub discipline: Discipline,
+    pub num_tasks: i64,
+    pub total_validity: Option<f64>,
+}
+
+/// Ta by the number of tasks and by task validity, marking the one the
+/// scoring rules on the competition date use
+#[function_component(TaskQualityInfo)]
+pub fn task_quality_info(props: &TaskQualityProps) -> Html {
+    let (comp_date, discipline, num_tasks, total_validity) = (
+        props.comp_date.clone(),
+        props.discipline,
+        props.num_tasks,
+        props.total_validity,
+    );
+    let task_quality = use_async(async move {
+        get_task_quality(comp_date, discipline, num_tasks, total_validity).await
+    });
+    {
+        let task_quality = task_quality.clone();
+        use_effect_with_deps(
+            move |_| {
+                task_quality.run();
+                || ()
+            },
+            (
+                props.comp_date.clone(),
+                props.discipline,
+                props.num_tasks,
+                props.total_validity.map(|v| v.to_string()),
+            ),
+        );
+    }
+    let in_use = |mode: TaskQualityMode, used: TaskQualityMode| match mode == used {
+        true => " (used)",
+        false => "",
+    };
+    match &task_quality.data {
+        Some(ta) => html! {
+            <p class="help">
+                {format!("Ta by number of tasks {:.2}{}", ta.by_task_count, in_use(TaskQualityMode::TaskCount, ta.mode))}
+                {", "}
+                {match ta.by_validity {
+                    Some(by_validity) => format!("Ta by task validity {:.2}{}", by_validity, in_use(TaskQualityMode::TaskValidity, ta.mode)),
+                    None => "no task validity to base Ta on".to_string(),
+                }}
+            </p>
+        },
+        None => html! {},
+    }
 }
 
 #[function_component(CompetitionCreate)]
 pub fn competition_create() -> Html {
     let (state, dispatch) = use_store::<Competition>();
     let import_input = use_state(|| "".to_string());
+    // Task scores and unread rows from the last import, the competition
+    // itself goes to the store
+    let import_report = use_state(ImportedCompetition::default);
+    let token = use_local_storage::<String>("auth".to_string());
+    // A CSV file waiting for its columns to be mapped before it is imported
+    let csv_upload = use_state(CsvUpload::default);
 
     let import_changed = {
         let handle = import_input.clone();
@@ -83,17 +203,13 @@ pub fn competition_create() -> Html {
     };
     let from_fai = {
         let handle = import_input.clone();
+        let report = import_report.clone();
         dispatch.reduce_mut_future_callback(move |state| {
             let v = handle.clone();
+            let report = report.clone();
             Box::pin(async move {
-                if let Ok(comp) = get_fai_comp(&v.to_string()).await {
-                    web_sys::console::log_1(&comp.name.clone().into());
-                    state.name = comp.name;
-                    state.location = comp.location;
-                    state.comp_date = comp.comp_date;
-                    state.num_tasks = comp.num_tasks;
-                    state.overseas = comp.overseas;
-                    state.placings = comp.placings;
+                if let Ok(imported) = get_fai_comp(&v.to_string()).await {
+                    apply_import(state, &report, imported);
                 }
                 ()
             })
@@ -102,31 +218,195 @@ pub fn competition_create() -> Html {
 
     let from_hc = {
         let handle = import_input.clone();
+        let report = import_report.clone();
         dispatch.reduce_mut_future_callback(move |state| {
             let v = handle.clone();
+            let report = report.clone();
             Box::pin(async move {
-                if let Ok(comp) = get_highcloud_comp(&v.to_string()).await {
-                    web_sys::console::log_1(&comp.name.clone().into());
-                    state.name = comp.name;
-                    state.location = comp.location;
-                    state.comp_date = comp.comp_date;
-                    state.num_tasks = comp.num_tasks;
-                    state.overseas = comp.overseas;
-                    state.placings = comp.placings;
+                if let Ok(imported) = get_highcloud_comp(&v.to_string()).await {
+                    apply_import(state, &report, imported);
                 }
                 ()
             })
         })
     };
 
+    let from_fsdb = {
+        let report = import_report.clone();
+        let token = token.clone();
+        dispatch.reduce_mut_future_callback_with(move |state, e: Event| {
+            let report = report.clone();
+            let token = token.as_ref().cloned().unwrap_or_default();
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let file = input.files().and_then(|files| files.get(0));
+            Box::pin(async move {
+                if let Some(file) = file {
+                    let file = gloo_file::File::from(file);
+                    if let Ok(contents) = gloo_file::futures::read_as_text(&file).await {
+                        if let Ok(imported) = upload_fsdb(contents, token).await {
+                            apply_import(state, &report, imported);
+                        }
+                    }
+                }
+            })
+        })
+    };
+
+    let from_csv = {
+        let csv_upload = csv_upload.clone();
+        Callback::from(move |e: Event| {
+            let csv_upload = csv_upload.clone();
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let file = input.files().and_then(|files| files.get(0));
+            yew::platform::spawn_local(async move {
+                if let Some(file) = file {
+                    let file = gloo_file::File::from(file);
+                    if let Ok(contents) = gloo_file::futures::read_as_text(&file).await {
+                        let first_row = read_csv(&contents)
+                            .into_iter()
+                            .next()
+                            .map(|(_, cells)| cells)
+                            .unwrap_or_default();
+                        csv_upload.set(CsvUpload {
+                            mapping: CsvMapping::guess(&first_row),
+                            contents,
+                        });
+                    }
+                }
+            });
+        })
+    };
+
+    let csv_column_changed = |field: CsvField| {
+        let csv_upload = csv_upload.clone();
+        Callback::from(move |e: Event| {
+            let element: HtmlSelectElement = e.target_unchecked_into();
+            let mut upload = (*csv_upload).clone();
+            // The first option leaves the field unmapped
+            let column = (element.selected_index() as usize).checked_sub(1);
+            upload.mapping.set_column(field, column);
+            csv_upload.set(upload);
+        })
+    };
+
+    let csv_header_changed = {
+        let csv_upload = csv_upload.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut upload = (*csv_upload).clone();
+            upload.mapping.has_header = !upload.mapping.has_header;
+            csv_upload.set(upload);
+        })
+    };
+
+    // Only the placings are taken from a CSV file, the rest of the
+    // competition is kept as entered
+    let import_csv = {
+        let csv_upload = csv_upload.clone();
+        let report = import_report.clone();
+        dispatch.reduce_mut_future_callback(move |state| {
+            let csv_upload = csv_upload.clone();
+            let report = report.clone();
+            let token = token.as_ref().cloned().unwrap_or_default();
+            Box::pin(async move {
+                if let Ok(imported) = upload_csv(&csv_upload, token).await {
+                    state.placings = imported.competition.placings.clone();
+                    report.set(ImportedCompetition {
+                        competition: Competition::default(),
+                        ..imported
+                    });
+                    csv_upload.set(CsvUpload::default());
+                }
+            })
+        })
+    };
+
+    let csv_rows: Vec<Vec<String>> = read_csv(&csv_upload.contents)
+        .into_iter()
+        .map(|(_, cells)| cells)
+        .collect();
+    let csv_columns = csv_rows.iter().map(Vec::len).max().unwrap_or_default();
+    let csv_column_name = |column: usize| match csv_upload.mapping.has_header {
+        true => csv_rows
+            .first()
+            .and_then(|header| header.get(column))
+            .cloned()
+            .unwrap_or_default(),
+        false => format!("Column {}", column + 1),
+    };
+
     fn exchange_rate_visible(state: &Rc<Competition>) -> Option<String> {
-        if state.overseas {
+        if state.kind == CompetitionKind::OverseasFai {
+            None
+        } else {
+            Some("is-hidden".to_string())
+        }
+    }
+
+    fn kind_visible(state: &Rc<Competition>) -> Option<String> {
+        if state.overseas() {
             None
         } else {
             Some("is-hidden".to_string())
         }
     }
 
+    let kind_changed = dispatch.reduce_mut_callback_with(|state, e: Event| {
+        let element: HtmlSelectElement = e.target_unchecked_into();
+        state.kind = match element.selected_index() {
+            1 => CompetitionKind::OverseasLeague,
+            _ => CompetitionKind::OverseasFai,
+        };
+    });
+
+    let discipline_changed = dispatch.reduce_mut_callback_with(|state, e: Event| {
+        let element: HtmlSelectElement = e.target_unchecked_into();
+        if let Some(discipline) = Discipline::ALL.get(element.selected_index() as usize) {
+            state.discipline = *discipline;
+        }
+    });
+
+    let status_changed = |index: usize| {
+        dispatch.reduce_mut_callback_with(move |state, e: Event| {
+            let element: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(status) = PlacingStatus::ALL.get(element.selected_index() as usize) {
+                if let Some(placing) = state.placings.get_mut(index) {
+                    placing.status = *status;
+                }
+            }
+        })
+    };
+
+    fn match_tag(pilot_match: Option<&PilotMatch>) -> Html {
+        match pilot_match.map(|m| m.kind) {
+            Some(PilotMatchKind::Pin) => {
+                html! {<span class="tag is-success is-light">{"matched by pin"}</span>}
+            }
+            Some(PilotMatchKind::Name) => {
+                html! {<span class="tag is-info is-light">{"matched by name"}</span>}
+            }
+            Some(PilotMatchKind::Unmatched) => {
+                html! {<span class="tag is-warning is-light">{"new pilot"}</span>}
+            }
+            None => html! {},
+        }
+    }
+
+    fn task_scores(scores: Option<&Vec<Option<f64>>>) -> String {
+        scores
+            .map(|scores| {
+                scores
+                    .iter()
+                    .map(|score| {
+                        score
+                            .map(|s| format!("{:.0}", s))
+                            .unwrap_or("-".to_string())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" / ")
+            })
+            .unwrap_or_default()
+    }
+
     fn submit_disabled(state: &Rc<Competition>) -> bool {
         match state.validate() {
             Err(_) => true,
@@ -157,6 +437,83 @@ pub fn competition_create() -> Html {
       <div class="control">
         <button class="button is-link" onclick={from_fai}>{"From FAI"}</button>
       </div>
+      <div class="control">
+        <div class="file is-link">
+          <label class="file-label">
+            <input class="file-input" type="file" accept=".fsdb" onchange={from_fsdb}/>
+            <span class="file-cta"><span class="file-label">{"From FS (.fsdb)"}</span></span>
+          </label>
+        </div>
+      </div>
+      <div class="control">
+        <div class="file is-link">
+          <label class="file-label">
+            <input class="file-input" type="file" accept=".csv" onchange={from_csv}/>
+            <span class="file-cta"><span class="file-label">{"From spreadsheet (.csv)"}</span></span>
+          </label>
+        </div>
+      </div>
+    </div>
+    {
+        if csv_rows.is_empty() {
+            html!{}
+        } else {
+            html!{
+            <div class="box">
+              <p class="help">{format!("{} rows, choose the column holding each field", csv_rows.len())}</p>
+              <div class="field">
+                <input id="csvHeader" type="checkbox" onclick={csv_header_changed} checked={csv_upload.mapping.has_header}/>
+                <label for="csvHeader">{" The first row names the columns"}</label>
+              </div>
+              <div class="field is-grouped is-grouped-multiline">
+              {
+                  CsvField::ALL.iter().map(|field| html!{
+                      <div class="control">
+                        <label class="label is-small">{field.name()}</label>
+                        <div class="select is-small">
+                          <select onchange={csv_column_changed(*field)}>
+                            <option selected={csv_upload.mapping.column(*field).is_none()}>{"—"}</option>
+                            {
+                                (0..csv_columns).map(|column| html!{
+                                    <option selected={csv_upload.mapping.column(*field) == Some(column)}>{csv_column_name(column)}</option>
+                                }).collect::<Html>()
+                            }
+                          </select>
+                        </div>
+                      </div>
+                  }).collect::<Html>()
+              }
+              </div>
+              <button class="button is-link" onclick={import_csv}>{"Import rows"}</button>
+            </div>
+            }
+        }
+    }
+    <div class="content">
+      {
+          match &import_report.wprs {
+              Some(wprs) => html!{
+                  <p>{format!(
+                      "WPRS category {} in {}{}, {} pilots, Pq {:.3} Pn {:.3} Ta {:.3}",
+                      wprs.category,
+                      wprs.country,
+                      wprs.end_date.as_ref().map(|date| format!(" until {}", date)).unwrap_or_default(),
+                      wprs.num_pilots,
+                      wprs.pq,
+                      wprs.pn,
+                      wprs.ta
+                  )}</p>
+              },
+              None => html!{},
+          }
+      }
+      <ul>
+      {
+          import_report.errors.iter().map(|error| html!{
+              <li class="has-text-danger">{format!("Row {}: {}", error.row + 1, error.message)}</li>
+          }).collect::<Html>()
+      }
+      </ul>
     </div>
       <div class="field">
         <label class="label">{"Name"}</label>
@@ -188,47 +545,107 @@ pub fn competition_create() -> Html {
           <input value={dispatch.get().num_tasks.to_string()}  type="number"  oninput={dispatch.input_mut(|state, text| state.num_tasks = text)} class={classes!("input",is_valid("num_tasks", &state))} type="text" placeholder="Number of tasks"/>
         </div>
         <p class="help is-danger">{validation_message("num_tasks",&state)}</p>
+        <p class="help">
+        {
+            match state.summed_validity() {
+                Some(validity) => format!("Task validity {} summing to {:.3}", state.task_validity.iter().map(|v| format!("{:.3}", v)).collect::<Vec<_>>().join(", "), validity),
+                None => "Task validity is not known".to_string(),
+            }
+        }
+        </p>
+        <TaskQualityInfo comp_date={state.comp_date.clone()} discipline={state.discipline} num_tasks={state.num_tasks} total_validity={state.summed_validity()}/>
       </div>
 
       <div class="field">
-        <input id="switchRoundedInfo" type="checkbox" onclick={dispatch.reduce_mut_callback(|state| state.overseas = !state.overseas)}  name="switchRoundedInfo" class="switch is-rounded is-info" checked={dispatch.get().overseas}/>
+        <label class="label">{"Discipline"}</label>
+        <div class="control">
+          <div class="select">
+            <select onchange={discipline_changed}>
+            {
+                Discipline::ALL.iter().map(|discipline| html!{
+                    <option selected={*discipline == state.discipline}>{discipline.name()}</option>
+                }).collect::<Html>()
+            }
+            </select>
+          </div>
+        </div>
+      </div>
+
+      <div class="field">
+        <input id="switchRoundedInfo" type="checkbox" onclick={dispatch.reduce_mut_callback(|state| state.kind = match state.kind { CompetitionKind::Local => CompetitionKind::OverseasFai, _ => CompetitionKind::Local })}  name="switchRoundedInfo" class="switch is-rounded is-info" checked={dispatch.get().overseas()}/>
         <label for="switchRoundedInfo">{"Overseas"}</label>
       </div>
 
+      <div class={classes!("field",kind_visible(&state))}>
+        <label class="label">{"Scored as"}</label>
+        <div class="control">
+          <div class="select">
+            <select onchange={kind_changed}>
+              <option selected={state.kind != CompetitionKind::OverseasLeague}>{"FAI competition (WPRS points by exchange rate)"}</option>
+              <option selected={state.kind == CompetitionKind::OverseasLeague}>{format!("League ({} or more NZ pilots, scored as local)", LEAGUE_MIN_PILOTS)}</option>
+            </select>
+          </div>
+        </div>
+      </div>
+
       <div class={classes!("field",exchange_rate_visible(&state))}>
         <label class="label">{"Exchange rate"}</label>
         <div class="control">
-          <input type="number" oninput={dispatch.input_mut(|state, text| state.exchange_rate = text)} class={classes!("input",is_valid("exchange_rate", &state))} type="text" placeholder="Exchange rate"/>
+          <input type="number" value={dispatch.get().exchange_rate.to_string()} oninput={dispatch.input_mut(|state, text| state.exchange_rate = text)} class={classes!("input",is_valid("exchange_rate", &state))} type="text" placeholder="Exchange rate"/>
         </div>
+        <p class="help">{"Imports from FAI suggest a rate from NZ competitions in the last two years that were also WPRS events"}</p>
         <p class="help is-danger">{validation_message("exchange_rate",&state)}</p>
       </div>
 
+      <div class="field">
+        <label class="label">{"CIVL competition id"}</label>
+        <div class="control">
+          <input value={dispatch.get().civl_id.clone().unwrap_or_default()} oninput={dispatch.input_mut(|state, text: String| state.civl_id = Some(text).filter(|id| !id.trim().is_empty()))} class="input" type="text" placeholder="For competitions that are also WPRS events"/>
+        </div>
+      </div>
+
       <div class="control">
         <table class="table is-fullwidth">
             <thead>
                 <tr>
                 <th>{"Rank"}</th>
+                <th>{"Status"}</th>
                 <th>{"Pin"}</th>
                 <th>{"Pilot"}</th>
+                <th>{"Task scores"}</th>
                 <th>{"Points"}</th>
                 </tr>
             </thead>
             <tbody>
             {
-                dispatch.get().placings.iter().map(|placing|
+                dispatch.get().placings.iter().enumerate().map(|(index, placing)|
                     html!{
                     <tr>
                         <td>{&placing.place}</td>
+                        <td>
+                          <div class="select is-small">
+                            <select onchange={status_changed(index)}>
+                            {
+                                PlacingStatus::ALL.iter().map(|status| html!{
+                                    <option selected={*status == placing.status}>{format!("{:?}", status)}</option>
+                                }).collect::<Html>()
+                            }
+                            </select>
+                          </div>
+                        </td>
                         <td>{&placing.pilot.pin}</td>
                         <td><Link<AppRoute> to={AppRoute::PilotDetail {pin: placing.pilot.pin.clone()}}>
                             {format!("{} {}", &placing.pilot.first_name, &placing.pilot.last_name )}
-                        </Link<AppRoute>></td>
+                        </Link<AppRoute>>
+                        {match_tag(import_report.matches.get(index))}</td>
+                        <td>{task_scores(import_report.task_scores.get(index))}</td>
                         <td>{&placing.fai_points}</td>
                     </tr>
                 }).collect::<Html>()
             }
             </tbody>
           </table>
+          <p class="help is-danger">{validation_message("placings",&state)}</p>
       </div>
 
       <div class="field is-grouped">
@@ -257,7 +674,7 @@ mod tests {
             location: "location".to_string(),
             comp_date: "2022-12-".to_string(),
             num_tasks: 4,
-            overseas: true,
+            kind: CompetitionKind::OverseasFai,
             ..Default::default()
         });
         let exchange_rate_message = validation_message(&"exchange_rate", &competition);
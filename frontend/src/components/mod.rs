@@ -7,4 +7,5 @@ pub mod not_found;
 pub mod pilots;
 pub mod progress_bar;
 pub mod ranking;
+pub mod ranking_comparison;
 pub mod user_profile;
@@ -1,6 +1,6 @@
 use crate::{
     components::progress_bar::Progress,
-    data::prs_data_types::{Competition, Pilot},
+    data::prs_data_types::{Competition, Pilot, PilotRankingHistory},
     data::*,
     routes::AppRoute,
 };
@@ -25,6 +25,14 @@ async fn get_pilot_competitions(pin: String) -> Result<Vec<Competition>, MultiEr
     get_data(format!("/pilot/{}/competitions", pin)).await
 }
 
+async fn get_pilot_history(pin: String) -> Result<Vec<PilotRankingHistory>, MultiError> {
+    get_data(format!("/pilot/{}/history", pin)).await
+}
+
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 300.0;
+const CHART_MARGIN: f64 = 40.0;
+
 #[function_component(PilotList)]
 pub fn pilot_list() -> Html {
     let pilots = use_async(async move { get_pilots().await });
@@ -95,6 +103,7 @@ pub fn pilot_detail(props: &PilotDetailProps) -> Html {
                 </div>
             </section>
             <section class="section">
+                <PilotRankingChart pin={pilot.pin.clone()}/>
                 <PilotCompetitionList pin={pilot.pin}/>
             </section>
             </>
@@ -157,3 +166,107 @@ pub fn pilot_competition_list(props: &PilotDetailProps) -> Html {
         }
     }
 }
+
+/// Points and position in each ranking, points on the left axis and position
+/// on the right with first place at the top
+#[function_component(PilotRankingChart)]
+pub fn pilot_ranking_chart(props: &PilotDetailProps) -> Html {
+    let pin = props.pin.clone();
+    let history = use_async(async move { get_pilot_history(pin).await });
+
+    if let Some(history) = history.data.clone() {
+        if history.is_empty() {
+            return html! {};
+        }
+        let max_points = history.iter().map(|h| h.total_points).fold(1.0, f64::max);
+        let max_position = history.iter().filter_map(|h| h.position).max().unwrap_or(1);
+        let plot_width = CHART_WIDTH - 2.0 * CHART_MARGIN;
+        let plot_height = CHART_HEIGHT - 2.0 * CHART_MARGIN;
+        let x = |i: usize| match history.len() {
+            1 => CHART_MARGIN + plot_width / 2.0,
+            n => CHART_MARGIN + plot_width * i as f64 / (n - 1) as f64,
+        };
+        let points_y = |points: f64| CHART_MARGIN + plot_height * (1.0 - points / max_points);
+        let position_y = |position: usize| match max_position {
+            1 => CHART_MARGIN,
+            max => CHART_MARGIN + plot_height * (position - 1) as f64 / (max - 1) as f64,
+        };
+        let points_line = history
+            .iter()
+            .enumerate()
+            .map(|(i, h)| format!("{:.1},{:.1}", x(i), points_y(h.total_points)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Unranked months break the position line
+        let position_lines: Vec<String> = history
+            .iter()
+            .enumerate()
+            .collect::<Vec<_>>()
+            .split(|(_, h)| h.position.is_none())
+            .filter(|run| !run.is_empty())
+            .map(|run| {
+                run.iter()
+                    .map(|(i, h)| {
+                        format!("{:.1},{:.1}", x(*i), position_y(h.position.unwrap_or(1)))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        let bottom = CHART_HEIGHT - CHART_MARGIN;
+        let right = CHART_WIDTH - CHART_MARGIN;
+        html! {
+            <div class="box">
+                <p class="heading">
+                    <span class="has-text-info">{"Points"}</span>{" and "}
+                    <span class="has-text-danger">{"position"}</span>
+                </p>
+                <svg viewBox={format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)} width="100%">
+                    <line x1={CHART_MARGIN.to_string()} y1={CHART_MARGIN.to_string()}
+                        x2={CHART_MARGIN.to_string()} y2={bottom.to_string()} stroke="grey"/>
+                    <line x1={CHART_MARGIN.to_string()} y1={bottom.to_string()}
+                        x2={right.to_string()} y2={bottom.to_string()} stroke="grey"/>
+                    <line x1={right.to_string()} y1={CHART_MARGIN.to_string()}
+                        x2={right.to_string()} y2={bottom.to_string()} stroke="grey"/>
+                    <text x="2" y={CHART_MARGIN.to_string()} font-size="12" fill="hsl(204, 86%, 53%)">
+                        {format!("{:.0}", max_points)}
+                    </text>
+                    <text x="2" y={bottom.to_string()} font-size="12" fill="hsl(204, 86%, 53%)">{"0"}</text>
+                    <text x={(right + 4.0).to_string()} y={CHART_MARGIN.to_string()} font-size="12"
+                        fill="hsl(348, 100%, 61%)">{"1"}</text>
+                    <text x={(right + 4.0).to_string()} y={bottom.to_string()} font-size="12"
+                        fill="hsl(348, 100%, 61%)">{max_position}</text>
+                    <text x={CHART_MARGIN.to_string()} y={(bottom + 16.0).to_string()} font-size="12">
+                        {&history[0].date}
+                    </text>
+                    <text x={right.to_string()} y={(bottom + 16.0).to_string()} font-size="12"
+                        text-anchor="end">
+                        {&history[history.len() - 1].date}
+                    </text>
+                    <polyline points={points_line} fill="none" stroke="hsl(204, 86%, 53%)" stroke-width="2"/>
+                    {
+                        position_lines.into_iter().map(|line| html!{
+                            <polyline points={line} fill="none" stroke="hsl(348, 100%, 61%)" stroke-width="2"/>
+                        }).collect::<Html>()
+                    }
+                    {
+                        history.iter().enumerate().map(|(i, h)| html!{
+                            <circle cx={format!("{:.1}", x(i))} cy={format!("{:.1}", points_y(h.total_points))}
+                                r="3" fill="hsl(204, 86%, 53%)">
+                                <title>{format!("{} {:.2} points from {} results", h.date, h.total_points,
+                                    h.counted_results.len())}</title>
+                            </circle>
+                        }).collect::<Html>()
+                    }
+                </svg>
+            </div>
+        }
+    } else {
+        if !history.loading {
+            history.run();
+        }
+        html! {
+            <Progress/>
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     components::progress_bar::Progress,
-    data::prs_data_types::Ranking,
+    data::prs_data_types::{Discipline, Ranking, RankingCategory},
     data::{prs_data_types::RankingPoint, *},
     routes::AppRoute,
 };
@@ -12,10 +12,43 @@ use yew_router::prelude::Link;
 #[derive(Properties, PartialEq)]
 pub struct RankingDetailProps {
     pub date: NaiveDate,
+    #[prop_or_default]
+    pub discipline: Discipline,
+    #[prop_or_default]
+    pub category: Option<String>,
 }
 
-async fn get_ranking(date: NaiveDate) -> Result<Ranking, MultiError> {
-    get_data(format!("/ranking/{}", date.format("%Y-%m-01").to_string())).await
+async fn get_ranking(
+    date: NaiveDate,
+    discipline: Discipline,
+    category: Option<String>,
+) -> Result<Ranking, MultiError> {
+    let category = match category {
+        Some(category) => format!("&category={}", category),
+        None => "".to_string(),
+    };
+    get_data(format!(
+        "/ranking/{}?discipline={}{}",
+        date.format("%Y-%m-01").to_string(),
+        discipline,
+        category
+    ))
+    .await
+}
+
+async fn get_categories() -> Result<Vec<RankingCategory>, MultiError> {
+    get_data("/categories".to_string()).await
+}
+
+fn ranking_route(date: NaiveDate, discipline: Discipline, category: &Option<String>) -> AppRoute {
+    match category {
+        Some(category) => AppRoute::CategoryRankingDetail {
+            date,
+            discipline,
+            category: category.clone(),
+        },
+        None => AppRoute::DisciplineRankingDetail { date, discipline },
+    }
 }
 
 fn next_ranking(date: NaiveDate) -> NaiveDate {
@@ -35,6 +68,8 @@ fn previous_ranking(date: NaiveDate) -> NaiveDate {
 #[function_component(RankingDetail)]
 pub fn ranking_detail(props: &RankingDetailProps) -> Html {
     let date = props.date.clone();
+    let discipline = props.discipline;
+    let category = props.category.clone();
     let initial_date = date.clone();
     let next_date = next_ranking(date.clone());
     let prev_date = previous_ranking(date.clone());
@@ -48,14 +83,24 @@ pub fn ranking_detail(props: &RankingDetailProps) -> Html {
         true => Some("is-hidden"),
         false => None,
     };
-    let ranking_request = use_async(async move { get_ranking(date.clone()).await });
+    let ranking_request = {
+        let category = category.clone();
+        use_async(async move { get_ranking(date.clone(), discipline, category).await })
+    };
+    let categories = use_async(async move { get_categories().await });
+    if categories.data.is_none() && categories.error.is_none() && !categories.loading {
+        categories.run();
+    }
     if ranking_request.loading {
         html! {
             <Progress/>
         }
     } else {
         if let Some(ranking) = &ranking_request.data {
-            if ranking.date != initial_date.format("%Y-%m-01").to_string() {
+            if ranking.date != initial_date.format("%Y-%m-01").to_string()
+                || ranking.discipline != discipline
+                || ranking.category != category
+            {
                 if !ranking_request.loading {
                     ranking_request.run();
                 }
@@ -65,7 +110,12 @@ pub fn ranking_detail(props: &RankingDetailProps) -> Html {
                 <section class="hero is-info">
                     <div class="hero-body">
                         <p class="title">
-                        {"Ranking"}
+                        {format!("{} ranking", discipline.name())}
+                        {
+                            categories.data.iter().flatten()
+                                .find(|c| Some(&c.id) == category.as_ref())
+                                .map(|c| format!(" - {}", c.name))
+                        }
                         </p>
                         <p class="sub-title">
                         {&ranking.date}
@@ -76,16 +126,16 @@ pub fn ranking_detail(props: &RankingDetailProps) -> Html {
                         <div class="container">
                             <ul>
                             <li class={classes!(active)}>
-                                <Link<AppRoute> to={AppRoute::RankingDetail { date: now}}>
+                                <Link<AppRoute> to={ranking_route(now, discipline, &category)}>
                                 {"Current"}
                                 </Link<AppRoute>>
                             </li>
                             <li>
-                                <Link<AppRoute> to={AppRoute::RankingDetail { date: prev_date}}>
+                                <Link<AppRoute> to={ranking_route(prev_date, discipline, &category)}>
                                 {"Previous month"}
                                 </Link<AppRoute>></li>
                             <li class={classes!(hidden)}>
-                                <Link<AppRoute> to={AppRoute::RankingDetail { date: next_date}}>
+                                <Link<AppRoute> to={ranking_route(next_date, discipline, &category)}>
                                 {"Next month"}
                                 </Link<AppRoute>>
                             </li>
@@ -95,10 +145,45 @@ pub fn ranking_detail(props: &RankingDetailProps) -> Html {
                     </div>
                 </section>
                 <section class="section">
+                    <div class="tabs is-toggle is-small">
+                        <ul>
+                        {
+                            Discipline::ALL.iter().map(|d| html!{
+                                <li class={classes!((*d == discipline).then_some("is-active"))}>
+                                    <Link<AppRoute> to={ranking_route(initial_date, *d, &category)}>
+                                    {d.name()}
+                                    </Link<AppRoute>>
+                                </li>
+                            }).collect::<Html>()
+                        }
+                        </ul>
+                    </div>
+                    <div class="tabs is-small">
+                        <ul>
+                        <li class={classes!(category.is_none().then_some("is-active"))}>
+                            <Link<AppRoute> to={ranking_route(initial_date, discipline, &None)}>
+                            {"Overall"}
+                            </Link<AppRoute>>
+                        </li>
+                        {
+                            categories.data.iter().flatten().map(|c| html!{
+                                <li class={classes!((Some(&c.id) == category.as_ref()).then_some("is-active"))}>
+                                    <Link<AppRoute> to={ranking_route(initial_date, discipline, &Some(c.id.clone()))}>
+                                    {&c.name}
+                                    </Link<AppRoute>>
+                                </li>
+                            }).collect::<Html>()
+                        }
+                        </ul>
+                    </div>
+                    <Link<AppRoute> to={AppRoute::RankingComparison { discipline, from: prev_date, to: initial_date }}>
+                        {"Changes since the previous month"}
+                    </Link<AppRoute>>
                     <table class="table is-fullwidth">
                     <thead>
                         <tr>
                         <th><abbr title="Rank">{"Rank"}</abbr></th>
+                        <th><abbr title="Places moved since the previous month">{"+/-"}</abbr></th>
                         <th>{"Name"}</th>
                         <th><abbr title="Points">{"Points"}</abbr></th>
                         <th class="is-hidden-mobile"><abbr title="Competitions that go into the ranking">{"Competitions"}</abbr></th>
@@ -130,11 +215,26 @@ pub fn ranking_detail(props: &RankingDetailProps) -> Html {
     }
 }
 
+fn movement(ranking_point: &RankingPoint) -> Html {
+    match (ranking_point.previous_position, ranking_point.movement) {
+        // New to the ranking, or stored before movement was kept
+        (None, _) | (_, None) => html! {<></>},
+        (Some(_), Some(0)) => html! {<span class="has-text-grey">{"-"}</span>},
+        (Some(_), Some(up)) if up > 0 => {
+            html! {<span class="has-text-success">{format!("▲{}", up)}</span>}
+        }
+        (Some(_), Some(down)) => {
+            html! {<span class="has-text-danger">{format!("▼{}", -down)}</span>}
+        }
+    }
+}
+
 fn ranking_point_list(ranking_points: &Vec<RankingPoint>) -> Html {
     ranking_points.iter().enumerate().map(|(i, ranking_point)|
         html!{
         <tr>
-            <td>{i+1}</td>
+            <td>{if ranking_point.position > 0 { ranking_point.position } else { i+1 }}</td>
+            <td>{movement(ranking_point)}</td>
             <td><strong>
             <Link<AppRoute> to={AppRoute::PilotDetail {pin: ranking_point.pilot_pin.clone() }}>
             {format!("{} {} ", &ranking_point.pilot_first_name, &ranking_point.pilot_last_name)}
@@ -0,0 +1,171 @@
+use crate::{
+    components::progress_bar::Progress,
+    data::prs_data_types::{ComparedCompetition, Discipline, PilotMovement, RankingComparison},
+    data::*,
+    routes::AppRoute,
+};
+use chrono::NaiveDate;
+use yew::prelude::*;
+use yew_hooks::use_async;
+use yew_router::prelude::Link;
+
+#[derive(Properties, PartialEq)]
+pub struct RankingComparisonProps {
+    pub discipline: Discipline,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+async fn get_comparison(
+    discipline: Discipline,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<RankingComparison, MultiError> {
+    get_data(format!(
+        "/rankings/compare?from={}&to={}&discipline={}",
+        from.format("%Y-%m-01"),
+        to.format("%Y-%m-01"),
+        discipline
+    ))
+    .await
+}
+
+#[function_component(RankingComparisonDetail)]
+pub fn ranking_comparison(props: &RankingComparisonProps) -> Html {
+    let (discipline, from, to) = (props.discipline, props.from, props.to);
+    let comparison = use_async(async move { get_comparison(discipline, from, to).await });
+
+    if let Some(comparison) = &comparison.data {
+        html! {
+            <>
+            <section class="hero is-info">
+                <div class="hero-body">
+                    <p class="title">
+                    {format!("{} ranking changes", comparison.discipline.name())}
+                    </p>
+                    <p class="sub-title">
+                    {format!("{} to {}", comparison.from, comparison.to)}
+                    </p>
+                </div>
+            </section>
+            <section class="section">
+                <nav class="level">
+                    <div class="level-item has-text-centered">
+                        <div>
+                        <p class="heading">{"Points from new competitions"}</p>
+                        <p class="title">{format!("{:.2}", comparison.points_from_new_competitions)}</p>
+                        </div>
+                    </div>
+                    <div class="level-item has-text-centered">
+                        <div>
+                        <p class="heading">{"Points lost to decay"}</p>
+                        <p class="title">{format!("{:.2}", comparison.points_lost_to_decay)}</p>
+                        </div>
+                    </div>
+                    <div class="level-item has-text-centered">
+                        <div>
+                        <p class="heading">{"Points dropped"}</p>
+                        <p class="title">{format!("{:.2}", comparison.points_dropped)}</p>
+                        </div>
+                    </div>
+                </nav>
+                <div class="columns">
+                    <div class="column">
+                        <h2 class="subtitle">{"Biggest climbers"}</h2>
+                        {movement_table(&comparison.climbers)}
+                    </div>
+                    <div class="column">
+                        <h2 class="subtitle">{"Biggest fallers"}</h2>
+                        {movement_table(&comparison.fallers)}
+                    </div>
+                </div>
+                <div class="columns">
+                    <div class="column">
+                        <h2 class="subtitle">{"New entries"}</h2>
+                        {movement_table(&comparison.new_entries)}
+                    </div>
+                    <div class="column">
+                        <h2 class="subtitle">{"Dropped out"}</h2>
+                        {movement_table(&comparison.dropped_out)}
+                    </div>
+                </div>
+                <div class="columns">
+                    <div class="column">
+                        <h2 class="subtitle">{"Competitions added"}</h2>
+                        {competition_list(&comparison.competitions_added)}
+                    </div>
+                    <div class="column">
+                        <h2 class="subtitle">{"Competitions aged out"}</h2>
+                        {competition_list(&comparison.competitions_aged_out)}
+                    </div>
+                </div>
+            </section>
+            </>
+        }
+    } else {
+        if comparison.error.is_some() {
+            return html! {
+                <section class="section"><h1 class="title">{"Rankings not found"}</h1>
+                <Link<AppRoute> to={AppRoute::RankingList} >{"Go to the current ranking"}</Link<AppRoute>>
+                </section>
+            };
+        }
+        if !comparison.loading {
+            comparison.run();
+        }
+        html! {
+            <Progress/>
+        }
+    }
+}
+
+fn position(position: Option<usize>) -> String {
+    position.map_or("-".to_string(), |p| p.to_string())
+}
+
+fn movement_table(movements: &[PilotMovement]) -> Html {
+    html! {
+        <table class="table is-fullwidth">
+        <thead>
+            <tr>
+            <th>{"Pilot"}</th>
+            <th>{"Was"}</th>
+            <th>{"Now"}</th>
+            <th>{"Points"}</th>
+            </tr>
+        </thead>
+        <tbody>
+        {
+            movements.iter().map(|m| html!{
+                <tr>
+                    <td>
+                        <Link<AppRoute> to={AppRoute::PilotDetail {pin: m.pilot_pin.clone()}}>
+                        {format!("{} {}", &m.pilot_first_name, &m.pilot_last_name)}
+                        </Link<AppRoute>>
+                    </td>
+                    <td>{position(m.from_position)}</td>
+                    <td>{position(m.to_position)}</td>
+                    <td>{format!("{:.2} → {:.2}", m.from_points, m.to_points)}</td>
+                </tr>
+            }).collect::<Html>()
+        }
+        </tbody>
+        </table>
+    }
+}
+
+fn competition_list(competitions: &[ComparedCompetition]) -> Html {
+    html! {
+        <ul>
+        {
+            competitions.iter().map(|c| html!{
+                <li>
+                    <Link<AppRoute> to={AppRoute::CompetitionDetail { id: c.comp_id.clone() }}>
+                    {format!("{} ({})", &c.comp_name, &c.comp_date)}
+                    </Link<AppRoute>>
+                </li>
+            }).collect::<Html>()
+        }
+        </ul>
+    }
+}
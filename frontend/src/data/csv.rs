@@ -0,0 +1,76 @@
+/// Read a CSV file as saved by a spreadsheet into its rows of cells, each with
+/// the line of the file it starts on counting from zero. Cells may be quoted,
+/// with quotes inside written twice, and hold commas or line breaks. Blank
+/// lines are left out
+pub fn read_csv(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut line = 0;
+    let mut row_line = 0;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                line += 1;
+                row.push(std::mem::take(&mut cell));
+                push_row(&mut rows, row_line, std::mem::take(&mut row));
+                row_line = line;
+            }
+            '\n' => {
+                line += 1;
+                cell.push(c);
+            }
+            _ => cell.push(c),
+        }
+    }
+    row.push(cell);
+    push_row(&mut rows, row_line, row);
+    rows
+}
+
+fn push_row(rows: &mut Vec<(usize, Vec<String>)>, line: usize, row: Vec<String>) {
+    if row.iter().any(|cell| !cell.trim().is_empty()) {
+        rows.push((
+            line,
+            row.into_iter()
+                .map(|cell| cell.trim().to_string())
+                .collect(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_csv_should_handle_quotes_and_line_endings() {
+        let rows = read_csv(
+            "Place,Name\r\n1,\"Smith, Ann\"\r\n\r\n2,\"Bob \"\"BJ\"\" Jones\"\n,,\n=3, Cat ",
+        );
+        assert_eq!(
+            rows,
+            vec![
+                (0, vec!["Place", "Name"]),
+                (1, vec!["1", "Smith, Ann"]),
+                (3, vec!["2", "Bob \"BJ\" Jones"]),
+                (5, vec!["=3", "Cat"])
+            ]
+        );
+        let rows = read_csv("Place,Name\n1,\"Ann\nSmith\"\n\n2,Bob");
+        assert_eq!(
+            rows.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![0, 1, 4]
+        );
+        assert!(read_csv("").is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use reqwest::StatusCode;
 
+pub mod csv;
 pub mod prs_data_types;
 
 pub async fn get_data<T>(path: String) -> Result<T, MultiError>
@@ -28,6 +29,36 @@ where
         .bearer_auth(&token.unwrap_or_default().as_str())
         .send()
         .await;
+    read_response(response).await
+}
+
+/// Post a body, such as an uploaded file's contents, as a signed in user
+pub async fn post_authorized_data<T>(
+    path: String,
+    body: String,
+    token: String,
+) -> Result<T, MultiError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let response = reqwest::ClientBuilder::new()
+        .build()
+        .ok()
+        .ok_or(MultiError::RequestError)?
+        .post(format!("{}{}", get_base_url(), path))
+        .bearer_auth(token.as_str())
+        .body(body)
+        .send()
+        .await;
+    read_response(response).await
+}
+
+async fn read_response<T>(
+    response: Result<reqwest::Response, reqwest::Error>,
+) -> Result<T, MultiError>
+where
+    T: serde::de::DeserializeOwned,
+{
     match response {
         Err(_) => Err(MultiError::RequestError),
         Ok(response) => match response.status() {
@@ -1,6 +1,7 @@
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::{fmt, str::FromStr};
 use validator::{Validate, ValidationError};
 use yewdux::store::Store;
 
@@ -20,6 +21,8 @@ pub struct Pilot {
     pub first_name: String,
     pub last_name: String,
     pub gender: String,
+    #[serde(default)]
+    pub birth_year: Option<i32>,
 }
 
 fn validate_date(date: &str) -> Result<(), ValidationError> {
@@ -33,15 +36,137 @@ fn validate_date(date: &str) -> Result<(), ValidationError> {
 }
 
 fn validate_overseas(competition: &Competition) -> Result<(), ValidationError> {
-    match competition.overseas {
-        true => match competition.exchange_rate.total_cmp(&0.1).is_lt() {
+    match competition.kind {
+        CompetitionKind::OverseasFai => match competition.exchange_rate.total_cmp(&0.1).is_lt() {
             true => Err(ValidationError {
                 message: Some("Please enter an exchange rate".into()),
                 ..ValidationError::new("exchange_rate")
             }),
             false => Ok(()),
         },
-        false => Ok(()),
+        CompetitionKind::OverseasLeague => {
+            let pilots = competition
+                .placings
+                .iter()
+                .filter(|p| p.status.is_participant())
+                .count();
+            match pilots < LEAGUE_MIN_PILOTS {
+                true => Err(ValidationError {
+                    message: Some(
+                        format!("A league needs at least {} NZ pilots", LEAGUE_MIN_PILOTS).into(),
+                    ),
+                    ..ValidationError::new("placings")
+                }),
+                false => Ok(()),
+            }
+        }
+        CompetitionKind::Local => Ok(()),
+    }
+}
+
+/// The kind of flying a competition is for. Each discipline has its own
+/// ranking scored only from its own competitions.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Discipline {
+    #[default]
+    ParaglidingXc,
+    HangGlidingXc,
+    Accuracy,
+}
+
+impl Discipline {
+    pub const ALL: [Discipline; 3] = [
+        Discipline::ParaglidingXc,
+        Discipline::HangGlidingXc,
+        Discipline::Accuracy,
+    ];
+
+    /// Identifier used in urls and storage, the same as the serialized name
+    pub fn id(&self) -> &'static str {
+        match self {
+            Discipline::ParaglidingXc => "paraglidingXc",
+            Discipline::HangGlidingXc => "hangGlidingXc",
+            Discipline::Accuracy => "accuracy",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Discipline::ParaglidingXc => "Paragliding XC",
+            Discipline::HangGlidingXc => "Hang gliding XC",
+            Discipline::Accuracy => "Accuracy",
+        }
+    }
+}
+
+impl fmt::Display for Discipline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl FromStr for Discipline {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Discipline::ALL
+            .into_iter()
+            .find(|d| d.id() == id)
+            .ok_or(format!("Unknown discipline {}", id))
+    }
+}
+
+/// Fewest NZ pilots in an overseas competition for it to be a league
+pub const LEAGUE_MIN_PILOTS: usize = 6;
+
+/// How a competition is scored
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompetitionKind {
+    #[default]
+    Local,
+    /// Overseas with enough NZ pilots to be scored as a local competition on
+    /// their order in the results
+    OverseasLeague,
+    /// Overseas with WPRS points converted by the exchange rate
+    OverseasFai,
+}
+
+/// Competitions saved before kinds existed only have `overseas`, which made
+/// them FAI competitions
+mod stored_kind {
+    use super::CompetitionKind;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct StoredKind {
+        #[serde(default)]
+        overseas: bool,
+        #[serde(default)]
+        kind: Option<CompetitionKind>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        kind: &CompetitionKind,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        StoredKind {
+            overseas: *kind != CompetitionKind::Local,
+            kind: Some(*kind),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<CompetitionKind, D::Error> {
+        let stored = StoredKind::deserialize(deserializer)?;
+        Ok(match (stored.kind, stored.overseas) {
+            (Some(kind), _) => kind,
+            (None, true) => CompetitionKind::OverseasFai,
+            (None, false) => CompetitionKind::Local,
+        })
     }
 }
 
@@ -54,7 +179,6 @@ pub struct Competition {
     pub name: String,
     #[validate(length(min = 3, max = 300, message = "Must be longer than 3 characters"))]
     pub location: String,
-    pub overseas: bool,
     pub exchange_rate: f64,
     #[validate(custom = "validate_date")]
     pub comp_date: String,
@@ -67,6 +191,119 @@ pub struct Competition {
     pub pn: f64,
     pub ta: f64,
     pub td: f64,
+    /// The competition's id on civlcomps.org when it is also a WPRS event
+    #[serde(default)]
+    pub civl_id: Option<String>,
+    /// Stored with the older `overseas` flag alongside it
+    #[serde(flatten, with = "stored_kind")]
+    pub kind: CompetitionKind,
+    #[serde(default)]
+    pub discipline: Discipline,
+    /// Validity of each task, 0 to 1, where the scoring system gives it
+    #[serde(default)]
+    pub task_validity: Vec<f64>,
+    /// Summed validity of the tasks as given by the scoring system
+    #[serde(default)]
+    pub total_validity: Option<f64>,
+}
+
+impl Competition {
+    pub fn overseas(&self) -> bool {
+        self.kind != CompetitionKind::Local
+    }
+
+    /// The summed validity of the tasks flown, None when it isn't known
+    pub fn summed_validity(&self) -> Option<f64> {
+        match (self.total_validity, self.task_validity.is_empty()) {
+            (Some(total), _) => Some(total),
+            (None, false) => Some(self.task_validity.iter().sum()),
+            (None, true) => None,
+        }
+    }
+}
+
+/// How a competition's Ta is worked out
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskQualityMode {
+    /// From the number of tasks flown
+    #[default]
+    TaskCount,
+    /// From the summed validity of the tasks, so a low validity task counts
+    /// for less than a full one. Competitions without validity figures fall
+    /// back to the task count.
+    TaskValidity,
+}
+
+/// Ta for a competition both ways and the one the scoring rules use
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskQuality {
+    pub mode: TaskQualityMode,
+    pub by_task_count: f64,
+    pub by_validity: Option<f64>,
+}
+
+/// How a pilot took part in a competition
+///
+/// | Status       | Pn  | Last place | Points and ranking results |
+/// |--------------|-----|------------|----------------------------|
+/// | Flew         | yes | yes        | yes                        |
+/// | Disqualified | yes | yes        | no                         |
+/// | DidNotFly    | no  | no         | no                         |
+/// | Absent       | no  | no         | no                         |
+/// | Withdrawn    | no  | no         | no                         |
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlacingStatus {
+    #[default]
+    Flew,
+    DidNotFly,
+    Absent,
+    Disqualified,
+    Withdrawn,
+}
+
+impl PlacingStatus {
+    pub const ALL: [PlacingStatus; 5] = [
+        PlacingStatus::Flew,
+        PlacingStatus::DidNotFly,
+        PlacingStatus::Absent,
+        PlacingStatus::Disqualified,
+        PlacingStatus::Withdrawn,
+    ];
+
+    /// Counted in the number of participants for Pn and for last place
+    pub fn is_participant(&self) -> bool {
+        matches!(self, PlacingStatus::Flew | PlacingStatus::Disqualified)
+    }
+
+    /// Earns points and a ranking result
+    pub fn earns_points(&self) -> bool {
+        matches!(self, PlacingStatus::Flew)
+    }
+
+    /// Short code used by results tables
+    pub fn code(&self) -> &'static str {
+        match self {
+            PlacingStatus::Flew => "",
+            PlacingStatus::DidNotFly => "DNF",
+            PlacingStatus::Absent => "ABS",
+            PlacingStatus::Disqualified => "DSQ",
+            PlacingStatus::Withdrawn => "WD",
+        }
+    }
+
+    /// Read a results table code, anything unrecognised is not a status
+    pub fn from_code(code: &str) -> Option<PlacingStatus> {
+        match code.trim().to_uppercase().as_str() {
+            "DNF" => Some(PlacingStatus::DidNotFly),
+            "ABS" => Some(PlacingStatus::Absent),
+            "DSQ" | "DQ" => Some(PlacingStatus::Disqualified),
+            "WD" | "WDR" => Some(PlacingStatus::Withdrawn),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -79,6 +316,12 @@ pub struct Placing {
     pub fai_points: f64,
     pub pp: f64,
     pub pplacing: f64,
+    #[serde(default)]
+    pub status: PlacingStatus,
+    /// The pilot's total score in the competition's own scoring, GAP points
+    /// for XC
+    #[serde(default)]
+    pub score: Option<f64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -96,6 +339,28 @@ pub struct Ranking {
     pub id: String,
     pub date: String,
     pub ranking_points: Vec<RankingPoint>,
+    /// Rankings saved before disciplines existed are paragliding XC
+    #[serde(default)]
+    pub discipline: Discipline,
+    /// Set on a ranking derived for a category, the overall ranking has none
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// A group of pilots with its own ranking derived from the overall one. Ages
+/// are the age a pilot turns in the year of the ranking date, pilots without a
+/// birth year are left out of categories with an age limit.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingCategory {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub gender: Option<String>,
+    #[serde(default)]
+    pub min_age: Option<i32>,
+    #[serde(default)]
+    pub max_age: Option<i32>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -107,6 +372,15 @@ pub struct RankingPoint {
     pub pilot_gender: Option<String>,
     pub results: Vec<CompResult>,
     pub total_points: f64,
+    /// Rank in the ranking, pilots on equal points share a position
+    #[serde(default)]
+    pub position: usize,
+    /// Position in the previous month's ranking
+    #[serde(default)]
+    pub previous_position: Option<usize>,
+    /// Places moved up since the previous month, negative when down
+    #[serde(default)]
+    pub movement: Option<i64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -120,6 +394,449 @@ pub struct CompResult {
     pub overseas: bool,
 }
 
+/// What a full recalculation changed compared with the stored values
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecalculationSummary {
+    pub applied: bool,
+    pub competitions_checked: usize,
+    pub rankings_checked: usize,
+    pub competitions: Vec<CompetitionChange>,
+    pub rankings: Vec<RankingChange>,
+}
+
+/// A newly saved competition as scored and what adding it changed in the
+/// later competitions and published rankings
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedCompetition {
+    pub competition: Competition,
+    pub recalculation: RecalculationSummary,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompetitionChange {
+    pub id: String,
+    pub name: String,
+    pub comp_date: String,
+    pub previous_comp_value: f64,
+    pub comp_value: f64,
+    pub previous_pq: f64,
+    pub pq: f64,
+    pub previous_pn: f64,
+    pub pn: f64,
+    pub placings_changed: usize,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingChange {
+    pub date: String,
+    pub discipline: Discipline,
+    pub pilots_changed: usize,
+    pub largest_difference: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PublishStatus {
+    Published,
+    Replaced,
+    Skipped,
+}
+
+/// The outcome of publishing the ranking for a month
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedRanking {
+    pub date: String,
+    pub discipline: Discipline,
+    pub status: PublishStatus,
+    pub pilots: usize,
+}
+
+/// Whether a result counted towards a pilot's ranking
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResultStatus {
+    Counted,
+    /// Outside the pilot's best results
+    NotInBest,
+    /// Dropped by the limit on overseas results
+    OverseasCap,
+}
+
+/// Every factor behind the points a pilot earned at one competition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultExplanation {
+    #[serde(rename = "compID")]
+    pub comp_id: String,
+    pub comp_name: String,
+    pub comp_date: String,
+    pub place: i64,
+    pub overseas: bool,
+    pub pplacing: f64,
+    pub pp: f64,
+    pub pq: f64,
+    pub pn: f64,
+    pub ta: f64,
+    pub fai_points: f64,
+    pub exchange_rate: f64,
+    pub points: f64,
+    pub td: f64,
+    pub decayed_points: f64,
+    pub status: ResultStatus,
+}
+
+/// How a pilot's ranking total is made up on a ranking date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsExplanation {
+    pub pilot_pin: String,
+    pub ranking_date: String,
+    pub rules_version: String,
+    pub results: Vec<ResultExplanation>,
+    pub total_points: f64,
+}
+
+/// A NZ competition used to work out the exchange rate
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateInput {
+    pub id: String,
+    pub name: String,
+    pub comp_date: String,
+    pub civl_id: String,
+    /// Pilots with both NZPRS and WPRS points
+    pub pilots: usize,
+    pub nzprs_average: f64,
+    pub wprs_average: f64,
+}
+
+/// The suggested WPRS to NZPRS exchange rate for an overseas competition
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRate {
+    pub date: String,
+    /// None when no NZ competitions in the period have WPRS points
+    pub exchange_rate: Option<f64>,
+    pub nzprs_average: f64,
+    pub wprs_average: f64,
+    pub competitions: Vec<ExchangeRateInput>,
+}
+
+/// How a pilot's place and points changed between two rankings
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PilotMovement {
+    pub pilot_pin: String,
+    pub pilot_first_name: String,
+    pub pilot_last_name: String,
+    pub from_position: Option<usize>,
+    pub to_position: Option<usize>,
+    pub from_points: f64,
+    pub to_points: f64,
+    /// Places moved up, negative when down
+    pub movement: Option<i64>,
+    /// Points from results counted in the later ranking but not the earlier
+    /// one, mostly from new competitions
+    pub points_from_new_competitions: f64,
+    /// Points lost to decay on results counted in both rankings
+    pub points_lost_to_decay: f64,
+    /// Points of results counted in the earlier ranking but not the later one,
+    /// as their competition aged out or they fell out of the best results
+    #[serde(default)]
+    pub points_dropped: f64,
+}
+
+/// A competition that entered or left the ranking window
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedCompetition {
+    #[serde(rename = "compID")]
+    pub comp_id: String,
+    pub comp_name: String,
+    pub comp_date: String,
+}
+
+/// What changed between two rankings in a discipline
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingComparison {
+    pub from: String,
+    pub to: String,
+    pub discipline: Discipline,
+    pub new_entries: Vec<PilotMovement>,
+    pub dropped_out: Vec<PilotMovement>,
+    /// Biggest climb first
+    pub climbers: Vec<PilotMovement>,
+    /// Biggest fall first
+    pub fallers: Vec<PilotMovement>,
+    pub points_from_new_competitions: f64,
+    pub points_lost_to_decay: f64,
+    #[serde(default)]
+    pub points_dropped: f64,
+    pub competitions_added: Vec<ComparedCompetition>,
+    pub competitions_aged_out: Vec<ComparedCompetition>,
+}
+
+/// A draft competition to score without saving, the ranking date defaults to
+/// the first of the month after the competition
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationRequest {
+    pub competition: Competition,
+    #[serde(default)]
+    pub ranking_date: Option<String>,
+}
+
+/// The draft competition as it would be scored and every pilot whose place or
+/// points in the ranking would change with it
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub competition: Competition,
+    pub ranking_date: String,
+    pub changes: Vec<PilotMovement>,
+}
+
+/// A planned competition's entry list to forecast its value from
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastRequest {
+    pub comp_date: String,
+    pub num_tasks: i64,
+    pub pins: Vec<String>,
+    #[serde(default)]
+    pub discipline: Discipline,
+}
+
+/// A top ranked pilot not yet entered and what their entry would add to the
+/// competition's value
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastAddition {
+    pub pilot_pin: String,
+    pub pilot_first_name: String,
+    pub pilot_last_name: String,
+    pub position: usize,
+    pub value_increase: f64,
+}
+
+/// The most a competition can be worth given who has entered
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompetitionForecast {
+    pub pq: f64,
+    pub pn: f64,
+    /// Ta if only one task is flown
+    pub ta_min: f64,
+    /// Ta if every planned task is flown
+    pub ta_max: f64,
+    /// The winner's points if every planned task is flown
+    pub winner_points: f64,
+    /// The winner's points if only one task is flown
+    pub winner_points_min: f64,
+    /// Largest increase first
+    pub additions: Vec<ForecastAddition>,
+}
+
+/// Where a pilot stood in one ranking, a position of None is unranked
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PilotRankingHistory {
+    pub date: String,
+    pub position: Option<usize>,
+    pub total_points: f64,
+    /// The results that made up the total
+    pub counted_results: Vec<CompResult>,
+}
+
+/// A results row an import couldn't read, rows count from zero
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+/// A draft competition from a results source, with the task scores it was
+/// scored from and the rows that were left out or only partly read
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedCompetition {
+    pub competition: Competition,
+    /// Each placing's task scores in the order of the placings, None where
+    /// there was no score
+    pub task_scores: Vec<Vec<Option<f64>>>,
+    pub errors: Vec<ImportRowError>,
+    /// CIVL's figures when the competition came from its WPRS results
+    #[serde(default)]
+    pub wprs: Option<WprsDetails>,
+    /// How each placing's pilot was matched, in the order of the placings
+    #[serde(default)]
+    pub matches: Vec<PilotMatch>,
+}
+
+/// A stored competition linked to its WPRS results, with the CIVL rows that
+/// matched no placing
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedCompetition {
+    pub competition: Competition,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// How an imported pilot was matched to a stored pilot
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PilotMatchKind {
+    Pin,
+    Name,
+    /// A new pilot, or one whose name is written differently
+    #[default]
+    Unmatched,
+}
+
+/// An imported pilot as the results source has them and the stored pilot
+/// they were matched to
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PilotMatch {
+    /// The pilot's id in the results source
+    pub source_id: String,
+    pub name: String,
+    pub matched_pin: Option<String>,
+    pub kind: PilotMatchKind,
+}
+
+/// A WPRS competition's figures as published by CIVL, the number of pilots
+/// counts every nation
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WprsDetails {
+    pub end_date: Option<String>,
+    pub category: String,
+    pub country: String,
+    pub num_pilots: usize,
+    pub pq: f64,
+    pub pn: f64,
+    pub ta: f64,
+}
+
+/// A value read from each row of an uploaded CSV results file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    Place,
+    Pin,
+    FirstName,
+    LastName,
+    Gender,
+    Score,
+}
+
+impl CsvField {
+    pub const ALL: [CsvField; 6] = [
+        CsvField::Place,
+        CsvField::Pin,
+        CsvField::FirstName,
+        CsvField::LastName,
+        CsvField::Gender,
+        CsvField::Score,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvField::Place => "Place",
+            CsvField::Pin => "Pin",
+            CsvField::FirstName => "First name",
+            CsvField::LastName => "Last name",
+            CsvField::Gender => "Gender",
+            CsvField::Score => "Score",
+        }
+    }
+
+    /// Whether a header cell names the field, ignoring case and spacing
+    fn matches_header(&self, header: &str) -> bool {
+        let header = header.to_lowercase().replace([' ', '_', '-'], "");
+        let names: &[&str] = match self {
+            CsvField::Place => &["place", "rank", "pos", "position"],
+            CsvField::Pin => &["pin", "nzhgpapin", "pinno"],
+            CsvField::FirstName => &["firstname", "first", "givenname"],
+            CsvField::LastName => &["lastname", "last", "surname", "familyname"],
+            CsvField::Gender => &["gender", "sex"],
+            CsvField::Score => &["score", "total", "points"],
+        };
+        names.contains(&header.as_str())
+    }
+}
+
+/// Which column of an uploaded CSV results file holds each field, columns
+/// count from zero
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvMapping {
+    /// The first row names the columns and isn't a result
+    pub has_header: bool,
+    pub place: Option<usize>,
+    pub pin: Option<usize>,
+    pub first_name: Option<usize>,
+    pub last_name: Option<usize>,
+    pub gender: Option<usize>,
+    pub score: Option<usize>,
+}
+
+impl CsvMapping {
+    pub fn column(&self, field: CsvField) -> Option<usize> {
+        match field {
+            CsvField::Place => self.place,
+            CsvField::Pin => self.pin,
+            CsvField::FirstName => self.first_name,
+            CsvField::LastName => self.last_name,
+            CsvField::Gender => self.gender,
+            CsvField::Score => self.score,
+        }
+    }
+
+    pub fn set_column(&mut self, field: CsvField, column: Option<usize>) {
+        let value = match field {
+            CsvField::Place => &mut self.place,
+            CsvField::Pin => &mut self.pin,
+            CsvField::FirstName => &mut self.first_name,
+            CsvField::LastName => &mut self.last_name,
+            CsvField::Gender => &mut self.gender,
+            CsvField::Score => &mut self.score,
+        };
+        *value = column;
+    }
+
+    /// Guess the columns from the file's first row. It is taken as a header
+    /// when any of its cells name a field
+    pub fn guess(first_row: &[String]) -> CsvMapping {
+        let mut mapping = CsvMapping::default();
+        for field in CsvField::ALL {
+            let column = first_row
+                .iter()
+                .position(|header| field.matches_header(header));
+            mapping.set_column(field, column);
+        }
+        mapping.has_header = CsvField::ALL
+            .iter()
+            .any(|field| mapping.column(*field).is_some());
+        mapping
+    }
+}
+
+/// A CSV results file uploaded to be imported with its column mapping
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvUpload {
+    pub mapping: CsvMapping,
+    pub contents: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct UserInfo {
     pub email: String,
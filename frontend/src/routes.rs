@@ -7,9 +7,11 @@ use crate::components::{
     not_found::NotFound,
     pilots::{PilotDetail, PilotList},
     ranking::RankingDetail,
+    ranking_comparison::RankingComparisonDetail,
     user_profile::UserProfile,
 };
 
+use crate::data::prs_data_types::Discipline;
 use chrono::NaiveDate;
 use yew::prelude::*;
 use yew_router::prelude::*;
@@ -30,6 +32,23 @@ pub enum AppRoute {
     RankingList,
     #[at("/ranking/:date")]
     RankingDetail { date: NaiveDate },
+    #[at("/ranking/:date/:discipline")]
+    DisciplineRankingDetail {
+        date: NaiveDate,
+        discipline: Discipline,
+    },
+    #[at("/ranking/:date/:discipline/:category")]
+    CategoryRankingDetail {
+        date: NaiveDate,
+        discipline: Discipline,
+        category: String,
+    },
+    #[at("/rankings/compare/:discipline/:from/:to")]
+    RankingComparison {
+        discipline: Discipline,
+        from: NaiveDate,
+        to: NaiveDate,
+    },
     #[at("/about")]
     About,
     #[at("/login")]
@@ -87,6 +106,22 @@ pub fn switch(routes: AppRoute) -> Html {
             <RankingDetail date={date}/>
             </>
         },
+        AppRoute::DisciplineRankingDetail { date, discipline } => html! {
+            <>
+            <Navbar />
+            <RankingDetail date={date} discipline={discipline}/>
+            </>
+        },
+        AppRoute::CategoryRankingDetail {
+            date,
+            discipline,
+            category,
+        } => html! {
+            <>
+            <Navbar />
+            <RankingDetail date={date} discipline={discipline} category={category}/>
+            </>
+        },
         AppRoute::RankingList => {
             let date = chrono::Utc::now();
             html! {
@@ -96,6 +131,16 @@ pub fn switch(routes: AppRoute) -> Html {
                 </>
             }
         }
+        AppRoute::RankingComparison {
+            discipline,
+            from,
+            to,
+        } => html! {
+            <>
+            <Navbar />
+            <RankingComparisonDetail discipline={discipline} from={from} to={to}/>
+            </>
+        },
         AppRoute::About => html! { <div><Navbar/><About/></div> },
         AppRoute::Login => html! { <div><Navbar/><Login/></div> },
         AppRoute::NotFound => html! { <><Navbar/><NotFound/></> },